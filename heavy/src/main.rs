@@ -63,5 +63,10 @@ async fn main() {
     // Ensure the IP is 0.0.0.0 to accept external connections if needed,
     // or 127.0.0.1 for local only. 0.0.0.0 is more general.
     println!("HEAVY Service (Controlled) listening on http://0.0.0.0:{}", port);
-    warp::serve(controlled_heavy_route).run(([0, 0, 0, 0], port)).await;
+
+    // TLS and HTTP/1.1+HTTP/2 tuning come from ServerConfig (env-driven), and shutdown drains
+    // in-flight requests on SIGINT/SIGTERM instead of killing them outright.
+    let routes = controlled_heavy_route.boxed();
+    let server_config = server_bootstrap::ServerConfig::from_env(([0, 0, 0, 0], port).into());
+    server_bootstrap::serve(server_config, routes).await;
 }
\ No newline at end of file
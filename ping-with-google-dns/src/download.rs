@@ -0,0 +1,185 @@
+// ---------------
+// Resumable Downloads
+// ---------------
+//
+// Streams a response body to disk and, if the connection drops mid-transfer, resumes with a
+// `Range: bytes=<offset>-` request instead of restarting the whole download from scratch.
+
+use futures_util::StreamExt;
+use reqwest::{Client, StatusCode};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+use warp::{Filter, Rejection, Reply};
+
+const MAX_RESUME_ATTEMPTS: u32 = 5;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(250);
+
+/// Process-wide counter mixed into every destination path, so overlapping downloads of the same
+/// URL (different clients, or a retry racing a still-running download) never collide on one
+/// file. See `destination_path_for`.
+static DOWNLOAD_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// Outcome of a resumable download: how much ended up on disk and how many times the transfer
+/// had to reconnect and resume.
+#[derive(Debug)]
+struct DownloadOutcome {
+    total_bytes: u64,
+    resume_attempts: u32,
+}
+
+/// Streams `url` to `destination`, resuming with `Range: bytes=<downloaded>-` whenever the
+/// stream drops mid-transfer, with a doubling backoff between attempts. Falls back to a full
+/// re-download from byte 0 if the server ignores `Range` (answers 200 instead of 206); gives up
+/// entirely if a stream error occurs after the server has shown it doesn't advertise
+/// `Accept-Ranges: bytes`, since there'd be nothing to resume from.
+async fn download_resumable(client: &Client, url: &str, destination: &PathBuf) -> Result<DownloadOutcome, String> {
+    let mut downloaded: u64 = 0;
+    let mut resume_attempts: u32 = 0;
+    let mut backoff = INITIAL_BACKOFF;
+    let mut server_supports_range = false;
+
+    let mut file = File::create(destination)
+        .await
+        .map_err(|e| format!("Failed to create '{}': {}", destination.display(), e))?;
+
+    loop {
+        let mut request = client.get(url);
+        if downloaded > 0 {
+            request = request.header("Range", format!("bytes={}-", downloaded));
+        }
+
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => {
+                resume_attempts = back_off_or_give_up(resume_attempts, &mut backoff, format!("connecting: {}", e)).await?;
+                continue;
+            }
+        };
+
+        if downloaded > 0 && response.status() != StatusCode::PARTIAL_CONTENT {
+            if response.status().is_success() {
+                // Server ignored our Range header and sent the whole body again - start over.
+                downloaded = 0;
+                file = File::create(destination)
+                    .await
+                    .map_err(|e| format!("Failed to truncate '{}': {}", destination.display(), e))?;
+            } else {
+                resume_attempts = back_off_or_give_up(
+                    resume_attempts,
+                    &mut backoff,
+                    format!("server returned {} while resuming", response.status()),
+                )
+                .await?;
+                continue;
+            }
+        } else if downloaded == 0 && !response.status().is_success() {
+            return Err(format!("Download failed with status {}", response.status()));
+        }
+
+        server_supports_range = server_supports_range
+            || response
+                .headers()
+                .get("accept-ranges")
+                .map(|value| value == "bytes")
+                .unwrap_or(false);
+
+        let mut body_stream = response.bytes_stream();
+        let mut stream_error: Option<String> = None;
+        while let Some(next_chunk) = body_stream.next().await {
+            match next_chunk {
+                Ok(chunk) => {
+                    file.write_all(&chunk)
+                        .await
+                        .map_err(|e| format!("Failed writing to '{}': {}", destination.display(), e))?;
+                    downloaded += chunk.len() as u64;
+                }
+                Err(e) => {
+                    stream_error = Some(e.to_string());
+                    break;
+                }
+            }
+        }
+
+        match stream_error {
+            None => return Ok(DownloadOutcome { total_bytes: downloaded, resume_attempts }),
+            Some(message) => {
+                if !server_supports_range {
+                    return Err(format!("Stream failed and server doesn't support resuming: {}", message));
+                }
+                resume_attempts = back_off_or_give_up(resume_attempts, &mut backoff, message).await?;
+            }
+        }
+    }
+}
+
+/// Sleeps for `backoff` (doubling it for next time) and returns the incremented attempt count,
+/// unless `MAX_RESUME_ATTEMPTS` has already been reached, in which case it gives up with `reason`.
+async fn back_off_or_give_up(attempts: u32, backoff: &mut Duration, reason: String) -> Result<u32, String> {
+    if attempts >= MAX_RESUME_ATTEMPTS {
+        return Err(format!("Giving up after {} attempt(s): {}", attempts, reason));
+    }
+    tokio::time::sleep(*backoff).await;
+    *backoff *= 2;
+    Ok(attempts + 1)
+}
+
+/// Derives a filesystem-safe destination path for `url` without trusting any part of the URL
+/// itself as a path component (which could otherwise escape the temp directory). Distinct from a
+/// pure hash of `url`: a per-call sequence number is mixed in too, so two downloads of the same
+/// URL running at once - from different clients, or a retry racing a still-running download -
+/// get distinct files instead of one `File::create` (which truncates) stomping on the other's
+/// in-progress write. The path is computed once per `download_handler` call and stays the same
+/// across that download's own internal resumes.
+fn destination_path_for(url: &str) -> PathBuf {
+    // FNV-1a, chosen for the same reason the rest of this project hand-rolls its wire formats:
+    // one stable, dependency-free hash is simpler than pulling in a crate for it.
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in url.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    let sequence = DOWNLOAD_SEQUENCE.fetch_add(1, Ordering::Relaxed);
+
+    let mut path = std::env::temp_dir();
+    path.push(format!("demo-service-download-{:016x}-{:016x}.bin", hash, sequence));
+    path
+}
+
+/// Pulls `url=...` out of a raw query string, percent-decoding its value.
+fn parse_url_param(raw_query: &str) -> Option<String> {
+    raw_query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == "url")
+        .map(|(_, value)| query_decode::decode_query_value(value))
+}
+
+/// Handles `GET /download?url=...`: downloads `url` to a temp file, resuming through dropped
+/// connections, and reports the total bytes written and how many resume attempts it took.
+async fn download_handler(raw_query: String) -> Result<String, Rejection> {
+    let url = match parse_url_param(&raw_query) {
+        Some(url) => url,
+        None => return Ok("Missing required 'url' query parameter".to_string()),
+    };
+
+    let client = Client::new();
+    let destination = destination_path_for(&url);
+
+    match download_resumable(&client, &url, &destination).await {
+        Ok(outcome) => Ok(format!(
+            "Downloaded {} bytes to {} ({} resume attempt(s)).",
+            outcome.total_bytes,
+            destination.display(),
+            outcome.resume_attempts
+        )),
+        Err(message) => Ok(format!("Download failed: {}", message)),
+    }
+}
+
+/// The `/download` warp route.
+pub fn route() -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::path("download").and(warp::query::raw()).and_then(download_handler)
+}
@@ -0,0 +1,79 @@
+// ---------------
+// trust-dns-backed `reqwest` resolver
+// ---------------
+//
+// Plugs the trust-dns `Resolver` into reqwest's connector via `dns::Resolve`, so outbound HTTPS
+// requests are resolved through our configured upstream name servers while reqwest still sees
+// the original hostname - preserving TLS SNI and certificate validation. Previously
+// `resolve_and_connect` resolved the name itself and connected straight to the bare IP with a
+// manual `Host` header, which broke both.
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use trust_dns_resolver::Resolver;
+
+/// Resolves hostnames for `reqwest` via a trust-dns `Resolver`, with an optional override table
+/// that pins specific hostnames to a fixed set of backend IP addresses instead of querying DNS
+/// for them at all. The table is IP-only, not IP:port: reqwest's connector always takes the port
+/// from the request URL and overwrites whatever port a custom `Resolve` hands back (see the
+/// placeholder-port comment in `resolve` below), so there is no way to pin a port through this
+/// mechanism - accepting one in config would silently do nothing.
+pub struct TrustDnsResolver {
+    resolver: Arc<Resolver>,
+    connect_to: Arc<HashMap<String, Vec<IpAddr>>>,
+}
+
+impl TrustDnsResolver {
+    pub fn new(resolver: Resolver, connect_to: HashMap<String, Vec<IpAddr>>) -> Self {
+        TrustDnsResolver {
+            resolver: Arc::new(resolver),
+            connect_to: Arc::new(connect_to),
+        }
+    }
+}
+
+/// A lightweight source of randomness using only std: a freshly constructed `RandomState` reads
+/// OS entropy for its SipHash keys, so hashing nothing and finishing still yields a different
+/// value each call - good enough for picking among a handful of pinned addresses.
+fn random_index(len: usize) -> usize {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+    if len == 0 {
+        return 0;
+    }
+    let value = RandomState::new().build_hasher().finish();
+    (value as usize) % len
+}
+
+impl Resolve for TrustDnsResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = Arc::clone(&self.resolver);
+        let connect_to = Arc::clone(&self.connect_to);
+
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+
+            if let Some(pinned_ips) = connect_to.get(&host) {
+                // Pinned by the connect_to override table: hand back every configured address,
+                // starting from a randomly chosen one each time. Port 0 here is the same
+                // placeholder as the DNS lookup path below - reqwest fills in the real port from
+                // the request URL regardless of what we return.
+                let mut addrs: Vec<SocketAddr> = pinned_ips.iter().map(|ip| SocketAddr::new(*ip, 0)).collect();
+                addrs.rotate_left(random_index(addrs.len()));
+                return Ok(Box::new(addrs.into_iter()) as Addrs);
+            }
+
+            let lookup = tokio::task::spawn_blocking(move || resolver.lookup_ip(host.as_str()))
+                .await
+                .map_err(|e| Box::new(e) as Box<dyn StdError + Send + Sync>)?
+                .map_err(|e| Box::new(e) as Box<dyn StdError + Send + Sync>)?;
+
+            // Port is filled in by reqwest from the request URL; 0 here is just a placeholder.
+            let addrs: Vec<SocketAddr> = lookup.iter().map(|ip| SocketAddr::new(ip, 0)).collect();
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
@@ -0,0 +1,329 @@
+// ---------------
+// HTTP Load Testing / Latency Profiling
+// ---------------
+//
+// A small, self-contained load tester: fires N concurrent requests at a target URL and reports
+// latency percentiles, throughput, and a DNS-vs-connect timing breakdown, behind `/bench`.
+
+use reqwest::Client;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio::sync::{Mutex, Semaphore};
+use warp::{Filter, Rejection, Reply};
+
+const DEFAULT_REQUEST_COUNT: usize = 20;
+const DEFAULT_CONCURRENCY: usize = 5;
+// `/bench` is unauthenticated and fires real outbound requests at a caller-supplied URL - these
+// cap how much load a single call can generate, so it can't be turned into a DoS/SSRF-amplification
+// primitive against an arbitrary third-party target.
+const MAX_REQUEST_COUNT: usize = 1000;
+const MAX_CONCURRENCY: usize = 50;
+
+/// When a connection to a request's host was actually established during this benchmark run:
+/// the instant the DNS lookup completed, and the instant the TCP connect that followed it did.
+#[derive(Debug, Clone, Copy)]
+struct ConnectionTiming {
+    dns_lookup: Instant,
+    dial_up: Instant,
+}
+
+/// The outcome of a single request fired during a benchmark run.
+#[derive(Debug, Clone)]
+struct RequestResult {
+    start: Instant,
+    end: Instant,
+    status: Option<u16>, // None if the request itself failed (e.g. connection refused)
+    len_bytes: usize,
+    connection_time: Option<ConnectionTiming>, // None when an already-pooled connection was reused
+}
+
+/// Parameters accepted on the `/bench` route.
+struct BenchParams {
+    url: String,
+    request_count: usize,
+    concurrency: usize,
+}
+
+impl BenchParams {
+    /// Parses `url=...&n=...&c=...` out of a raw query string (this crate parses its own wire
+    /// formats elsewhere too, rather than pulling in a query-string derive for one route).
+    fn parse(raw_query: &str) -> Result<BenchParams, String> {
+        let mut url: Option<String> = None;
+        let mut request_count = DEFAULT_REQUEST_COUNT;
+        let mut concurrency = DEFAULT_CONCURRENCY;
+
+        for pair in raw_query.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("Malformed query parameter: '{}'", pair))?;
+            match key {
+                "url" => url = Some(query_decode::decode_query_value(value)),
+                "n" => request_count = value
+                    .parse()
+                    .map_err(|_| format!("Invalid 'n' value: '{}'", value))?,
+                "c" => concurrency = value
+                    .parse()
+                    .map_err(|_| format!("Invalid 'c' value: '{}'", value))?,
+                _ => {} // Ignore unknown parameters
+            }
+        }
+
+        let url = url.ok_or_else(|| "Missing required 'url' query parameter".to_string())?;
+        if request_count == 0 {
+            return Err("'n' must be at least 1".to_string());
+        }
+        if request_count > MAX_REQUEST_COUNT {
+            return Err(format!("'n' must be at most {}", MAX_REQUEST_COUNT));
+        }
+        if concurrency == 0 {
+            return Err("'c' must be at least 1".to_string());
+        }
+        if concurrency > MAX_CONCURRENCY {
+            return Err(format!("'c' must be at most {}", MAX_CONCURRENCY));
+        }
+        if concurrency > request_count {
+            return Err("'c' must not be greater than 'n'".to_string());
+        }
+
+        Ok(BenchParams { url, request_count, concurrency })
+    }
+}
+
+/// Extracts the host (no scheme, no port) from a URL like `https://example.com:8443/path`.
+fn extract_host(url: &str) -> Option<String> {
+    let after_scheme = url.split("://").nth(1)?;
+    let host_and_port = after_scheme.split('/').next().unwrap_or(after_scheme);
+    let host_and_port = host_and_port.rsplit('@').next().unwrap_or(host_and_port); // Strip userinfo, if any
+    let host = host_and_port.split(':').next().unwrap_or(host_and_port); // Strip a port, if present
+    if host.is_empty() {
+        None
+    } else {
+        Some(host.to_string())
+    }
+}
+
+/// Extracts the port from a URL like `https://example.com:8443/path`, falling back to the
+/// scheme's default (443 for `https://`, 80 otherwise) when none is given explicitly.
+fn extract_port(url: &str) -> u16 {
+    let default_port = if url.starts_with("https://") { 443 } else { 80 };
+    let after_scheme = match url.split("://").nth(1) {
+        Some(rest) => rest,
+        None => return default_port,
+    };
+    let host_and_port = after_scheme.split('/').next().unwrap_or(after_scheme);
+    let host_and_port = host_and_port.rsplit('@').next().unwrap_or(host_and_port); // Strip userinfo, if any
+    host_and_port
+        .rsplit_once(':')
+        .and_then(|(_, port)| port.parse().ok())
+        .unwrap_or(default_port)
+}
+
+/// Fires a single GET request against `url`, returning its timing/outcome. Probes a fresh DNS
+/// lookup + TCP connect only the first time this benchmark run talks to `host`; later requests
+/// reuse the `reqwest` client's pooled connection, so `connection_time` is `None` for those.
+async fn fire_request(
+    client: Client,
+    url: String,
+    host: String,
+    port: u16,
+    established_hosts: Arc<Mutex<HashSet<String>>>,
+) -> RequestResult {
+    let start = Instant::now();
+
+    // Only the request that actually wins the insert should probe; the lock itself must not be
+    // held across that probe's await, or every other concurrently-spawned request queues up
+    // behind it just to check this set, collapsing `-c N` concurrency to serial execution (and
+    // baking the queueing delay into their measured latency).
+    let should_probe = {
+        let mut seen_hosts = established_hosts.lock().await;
+        seen_hosts.insert(host.clone())
+    };
+    let connection_time = if should_probe { probe_connection(&host, port).await } else { None };
+
+    let (status, len_bytes) = match client.get(&url).send().await {
+        Ok(response) => {
+            let status = Some(response.status().as_u16());
+            let len_bytes = response.bytes().await.map(|body| body.len()).unwrap_or(0);
+            (status, len_bytes)
+        }
+        Err(_) => (None, 0),
+    };
+
+    RequestResult {
+        start,
+        end: Instant::now(),
+        status,
+        len_bytes,
+        connection_time,
+    }
+}
+
+/// Times a standalone DNS lookup and TCP connect against `host`. The connection is dropped
+/// immediately afterwards - it exists purely to report the timing breakdown, since the actual
+/// request above goes through the (pooled) `reqwest` client instead.
+async fn probe_connection(host: &str, port: u16) -> Option<ConnectionTiming> {
+    let mut addrs = tokio::net::lookup_host((host, port)).await.ok()?;
+    let dns_lookup = Instant::now();
+    let addr = addrs.next()?;
+    TcpStream::connect(addr).await.ok()?;
+    let dial_up = Instant::now();
+    Some(ConnectionTiming { dns_lookup, dial_up })
+}
+
+/// Aggregate statistics computed from a batch of `RequestResult`s.
+struct BenchSummary {
+    requests: usize,
+    wall_clock: Duration,
+    total_bytes: usize,
+    min_latency_ms: f64,
+    mean_latency_ms: f64,
+    p50_latency_ms: f64,
+    p90_latency_ms: f64,
+    p99_latency_ms: f64,
+    connections_established: usize,
+    mean_dns_lookup_ms: Option<f64>,
+    mean_dial_up_ms: Option<f64>,
+    status_histogram: HashMap<String, usize>, // "200" -> count, or "error" for failed requests
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().sum::<f64>() / values.len() as f64)
+    }
+}
+
+fn summarize(results: &[RequestResult], wall_clock: Duration) -> BenchSummary {
+    let mut latencies_ms: Vec<f64> = results
+        .iter()
+        .map(|result| result.end.duration_since(result.start).as_secs_f64() * 1000.0)
+        .collect();
+    latencies_ms.sort_by(|a, b| a.partial_cmp(b).expect("latency is never NaN"));
+
+    let mut status_histogram: HashMap<String, usize> = HashMap::new();
+    for result in results {
+        let key = match result.status {
+            Some(code) => code.to_string(),
+            None => "error".to_string(),
+        };
+        *status_histogram.entry(key).or_insert(0) += 1;
+    }
+
+    let mut dns_lookup_ms = Vec::new();
+    let mut dial_up_ms = Vec::new();
+    for result in results {
+        if let Some(timing) = &result.connection_time {
+            dns_lookup_ms.push(timing.dns_lookup.duration_since(result.start).as_secs_f64() * 1000.0);
+            dial_up_ms.push(timing.dial_up.duration_since(timing.dns_lookup).as_secs_f64() * 1000.0);
+        }
+    }
+
+    let percentile = |p: f64| -> f64 {
+        if latencies_ms.is_empty() {
+            return 0.0;
+        }
+        let rank = ((p / 100.0) * (latencies_ms.len() - 1) as f64).round() as usize;
+        latencies_ms[rank]
+    };
+
+    BenchSummary {
+        requests: results.len(),
+        wall_clock,
+        total_bytes: results.iter().map(|result| result.len_bytes).sum(),
+        min_latency_ms: latencies_ms.first().copied().unwrap_or(0.0),
+        mean_latency_ms: mean(&latencies_ms).unwrap_or(0.0),
+        p50_latency_ms: percentile(50.0),
+        p90_latency_ms: percentile(90.0),
+        p99_latency_ms: percentile(99.0),
+        connections_established: dns_lookup_ms.len(),
+        mean_dns_lookup_ms: mean(&dns_lookup_ms),
+        mean_dial_up_ms: mean(&dial_up_ms),
+        status_histogram,
+    }
+}
+
+fn format_summary(summary: &BenchSummary) -> String {
+    let mut status_lines: Vec<String> = summary
+        .status_histogram
+        .iter()
+        .map(|(status, count)| format!("  {}: {}", status, count))
+        .collect();
+    status_lines.sort();
+
+    let requests_per_sec = summary.requests as f64 / summary.wall_clock.as_secs_f64().max(f64::EPSILON);
+    let format_ms = |ms: Option<f64>| ms.map(|v| format!("{:.1}ms", v)).unwrap_or_else(|| "n/a".to_string());
+
+    format!(
+        "Benchmark complete: {} requests in {:.2?}\n\
+         Requests/sec: {:.1}\n\
+         Total bytes received: {}\n\
+         Latency (ms) - min: {:.1}, mean: {:.1}, p50: {:.1}, p90: {:.1}, p99: {:.1}\n\
+         Connections established: {} (DNS lookup avg: {}, dial-up avg: {})\n\
+         Status codes:\n{}",
+        summary.requests,
+        summary.wall_clock,
+        requests_per_sec,
+        summary.total_bytes,
+        summary.min_latency_ms,
+        summary.mean_latency_ms,
+        summary.p50_latency_ms,
+        summary.p90_latency_ms,
+        summary.p99_latency_ms,
+        summary.connections_established,
+        format_ms(summary.mean_dns_lookup_ms),
+        format_ms(summary.mean_dial_up_ms),
+        status_lines.join("\n"),
+    )
+}
+
+/// Fires `request_count` GET requests at `url`, capped at `concurrency` in flight at once, and
+/// returns the aggregated result.
+async fn run_benchmark(params: BenchParams) -> BenchSummary {
+    let client = Client::new();
+    let established_hosts: Arc<Mutex<HashSet<String>>> = Arc::new(Mutex::new(HashSet::new()));
+    let semaphore = Arc::new(Semaphore::new(params.concurrency));
+    let host = extract_host(&params.url).unwrap_or_default();
+    let port = extract_port(&params.url);
+
+    let wall_clock_start = Instant::now();
+    let mut handles = Vec::with_capacity(params.request_count);
+    for _ in 0..params.request_count {
+        let client = client.clone();
+        let url = params.url.clone();
+        let host = host.clone();
+        let established_hosts = established_hosts.clone();
+        let semaphore = Arc::clone(&semaphore);
+        handles.push(tokio::spawn(async move {
+            let _permit = semaphore.acquire_owned().await.expect("benchmark semaphore was closed early");
+            fire_request(client, url, host, port, established_hosts).await
+        }));
+    }
+
+    let mut results = Vec::with_capacity(params.request_count);
+    for handle in handles {
+        if let Ok(result) = handle.await {
+            results.push(result);
+        }
+    }
+
+    summarize(&results, wall_clock_start.elapsed())
+}
+
+/// Handles `GET /bench?url=...&n=...&c=...`: runs a small concurrent load test against `url`
+/// and reports latency percentiles, throughput, and a DNS-vs-connect timing breakdown.
+async fn bench_handler(raw_query: String) -> Result<String, Rejection> {
+    let params = match BenchParams::parse(&raw_query) {
+        Ok(params) => params,
+        Err(message) => return Ok(message),
+    };
+
+    Ok(format_summary(&run_benchmark(params).await))
+}
+
+/// The `/bench` warp route.
+pub fn route() -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::path("bench").and(warp::query::raw()).and_then(bench_handler)
+}
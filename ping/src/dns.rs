@@ -1,9 +1,12 @@
 use std::fs::File;
-use std::io::{Read, ErrorKind};
+use std::io::{Read, Write, ErrorKind};
 use std::path::Path;
-use std::net::{UdpSocket, Ipv4Addr}; // SocketAddr is implicitly used by UdpSocket
+use std::net::{UdpSocket, TcpListener, TcpStream, Ipv4Addr, Ipv6Addr, IpAddr}; // SocketAddr is implicitly used by UdpSocket
 use std::collections::HashMap;
 use std::convert::TryInto;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
 
 // ---------------
 // Data Structures for Extracted Configuration (from Protobuf)
@@ -306,21 +309,48 @@ fn parse_configuration_file_proto(mut data: &[u8]) -> Result<Vec<ExtractedInfo>,
 // ---------------
 const QTYPE_A: u16 = 1;    // DNS A record type (IPv4 address)
 const QTYPE_TXT: u16 = 16;   // DNS TXT record type (text strings)
+const QTYPE_AAAA: u16 = 28;  // DNS AAAA record type (IPv6 address)
+const QTYPE_SRV: u16 = 33;   // DNS SRV record type (service location: priority/weight/port/target)
 const QCLASS_IN: u16 = 1;  // DNS INternet class
 
+// EDNS0 (RFC 6891): the OPT pseudo-record carried in the Additional section.
+const TYPE_OPT: u16 = 41;
+// Classic DNS-over-UDP payload limit, used when the client does not advertise EDNS0 support.
+const DEFAULT_UDP_PAYLOAD_SIZE: u16 = 512;
+// The maximum UDP payload size we are willing to advertise (and produce) ourselves.
+const OUR_MAX_UDP_PAYLOAD_SIZE: u16 = 4096;
+
+// DNS SOA record type, used in the Authority section of negative responses so resolvers can
+// derive a negative-cache TTL (RFC 2308).
+const TYPE_SOA: u16 = 6;
+// Zone apex this server is authoritative for. All SOA records are owned by this name.
+const ZONE_APEX: &str = "demo-service.internal.";
+// SOA fields (RFC 1035 3.3.13). This server doesn't do zone transfers, so these are static
+// rather than tracking a real zone file.
+const SOA_MNAME: &str = "demo-service.internal."; // Primary name server for the zone
+const SOA_RNAME: &str = "admin.demo-service.internal."; // Zone admin mailbox, dot-encoded
+const SOA_SERIAL: u32 = 1;
+const SOA_REFRESH: u32 = 3600;
+const SOA_RETRY: u32 = 600;
+const SOA_EXPIRE: u32 = 86400;
+// Doubles as the negative-cache TTL (RFC 2308 section 4): how long a resolver should cache
+// this NXDOMAIN/NODATA response for.
+const SOA_MINIMUM_TTL: u32 = 120;
+
 // DNS Header Flags (for responses)
 const FLAG_QR_RESPONSE: u16 = 0x8000; // Query/Response: 1 for response
 const FLAG_AA: u16 = 0x0400;          // Authoritative Answer: 1 (our server is authoritative for its configured names)
+const FLAG_TC: u16 = 0x0200;          // Truncation: 1 when the answer section was cut to fit the negotiated payload size
 
 // DNS Response Codes (RCODE)
 const RCODE_NO_ERROR: u16 = 0;        // No error condition
-// const RCODE_FORMAT_ERROR: u16 = 1; // Not fully used for sending, but could be
-const RCODE_SERVER_FAILURE: u16 = 2;
+const RCODE_FORMAT_ERROR: u16 = 1;    // Malformed / unparseable query
 const RCODE_NXDOMAIN: u16 = 3;        // Non-Existent Domain
-// const RCODE_NOT_IMPLEMENTED: u16 = 4; // Query type not implemented
+const RCODE_NOT_IMPLEMENTED: u16 = 4; // Unsupported opcode
+const RCODE_REFUSED: u16 = 5;         // Unsupported query class
 
 /// Represents a parsed DNS question from a query packet.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct DnsQuestion {
     qname: String, // Decoded domain name (e.g., "service-alpha" or "host.example.com")
     qtype: u16,    // Query type (e.g., A, TXT)
@@ -331,8 +361,23 @@ struct DnsQuestion {
 #[derive(Debug)]
 struct DnsQueryInfo {
     transaction_id: u16, // Copied to the response
-    question: DnsQuestion,
+    questions: Vec<DnsQuestion>, // QDCOUNT questions, in wire order
     // client_flags: u16, // Could store original flags if needed for complex logic
+    /// The client's advertised UDP payload size from an EDNS0 OPT record in the Additional
+    /// section, if present. `None` means the client did not send EDNS0, so we must stick to
+    /// `DEFAULT_UDP_PAYLOAD_SIZE`.
+    edns_udp_payload_size: Option<u16>,
+}
+
+/// Describes why a query could not be answered normally, carrying whatever was recoverable
+/// from the packet (at minimum the transaction id, when the header could be read) so a proper
+/// DNS error response can still be sent instead of silently dropping the datagram.
+#[derive(Debug)]
+struct DnsQueryError {
+    rcode: u16,
+    message: String,
+    transaction_id: Option<u16>,
+    questions: Vec<DnsQuestion>, // Whichever questions were fully parsed before the failure
 }
 
 // ---------------
@@ -352,34 +397,74 @@ fn bytes_to_u16_be(bytes: &[u8]) -> Result<u16, String> {
         .map_err(|e| format!("Failed to convert slice to [u8; 2]: {:?}", e))?))
 }
 
+/// Maximum number of compression-pointer indirections allowed while parsing a single QNAME.
+/// This mirrors the loop-protection fix from the dnsguide `read_qname` routine: without a cap,
+/// a maliciously self-referential packet could make the parser follow pointers forever.
+const MAX_QNAME_POINTER_JUMPS: u32 = 16;
+
 /// Parses a DNS QNAME from a packet slice starting at `start_offset`.
-/// Returns the decoded name string and the number of bytes read for the QNAME.
-/// This simplified version does not handle DNS name compression pointers.
+/// Returns the decoded name string and the number of bytes consumed from `start_offset`
+/// (i.e. how far the caller should advance its own cursor in the packet).
+///
+/// Supports DNS name compression pointers (RFC 1035 4.1.4): whenever a label length byte has
+/// its top two bits set (`0xC0`), the low 6 bits of that byte plus the following byte form a
+/// 14-bit offset into `packet_data` from which the name continues. A pointer always terminates
+/// the name for length-accounting purposes (it is exactly 2 bytes), even though the parser keeps
+/// following pointers to assemble the full label sequence. To prevent a crafted packet from
+/// looping forever, we cap the number of pointer jumps and reject any pointer that targets the
+/// same offset or a later offset than the one it was read from (compression pointers must always
+/// point strictly backwards).
 fn parse_qname_from_dns_packet(packet_data: &[u8], start_offset: usize) -> Result<(String, usize), String> {
     let mut qname_parts: Vec<String> = Vec::new();
-    let mut current_pos_in_packet = start_offset;
-    let mut total_qname_bytes_read_from_offset = 0;
+
+    // `read_pos` is the cursor used to actually read labels/pointers and can jump around the
+    // packet. `consumed_from_start` tracks bytes read from `start_offset` and keeps advancing
+    // normally until the first pointer is encountered, at which point it freezes (a pointer is
+    // always 2 bytes and terminates the name for length-accounting purposes) even though
+    // `read_pos` keeps jumping around to assemble the rest of the labels.
+    let mut read_pos = start_offset;
+    let mut consumed_from_start = 0usize;
+    let mut pointer_seen = false;
+    let mut pointer_jumps = 0u32;
 
     loop {
-        if current_pos_in_packet >= packet_data.len() {
+        if read_pos >= packet_data.len() {
             return Err("Buffer too short while reading QNAME label length".to_string());
         }
-        let label_len_byte = packet_data[current_pos_in_packet];
+        let label_len_byte = packet_data[read_pos];
 
         // Check for DNS name compression pointer (MSB two bits are 11)
         if (label_len_byte & 0xC0) == 0xC0 {
-            // This basic implementation does not support compression pointers in questions.
-            // A production server would need to handle this, potentially by looking up the name
-            // from an earlier offset in the original packet_data.
-            return Err("DNS name compression pointers in QNAME are not supported by this parser.".to_string());
-            // If we were to handle it (partially, just skipping the pointer):
-            // if current_pos_in_packet + 1 >= packet_data.len() { return Err("Buffer too short for QNAME compression pointer offset".to_string()); }
-            // total_qname_bytes_read_from_offset += 2; // Pointer is 2 bytes
-            // break; // A pointer always terminates the current name part.
+            if read_pos + 1 >= packet_data.len() {
+                return Err("Buffer too short for QNAME compression pointer offset".to_string());
+            }
+
+            if !pointer_seen {
+                consumed_from_start = (read_pos - start_offset) + 2;
+                pointer_seen = true;
+            }
+
+            pointer_jumps += 1;
+            if pointer_jumps > MAX_QNAME_POINTER_JUMPS {
+                return Err(format!(
+                    "Too many DNS name compression pointer indirections (max {})",
+                    MAX_QNAME_POINTER_JUMPS
+                ));
+            }
+
+            let pointer_offset = (((label_len_byte & 0x3F) as usize) << 8) | (packet_data[read_pos + 1] as usize);
+            if pointer_offset >= read_pos {
+                return Err("DNS name compression pointer does not point strictly backwards".to_string());
+            }
+
+            read_pos = pointer_offset;
+            continue;
         }
 
-        current_pos_in_packet += 1; // Advance past the length byte
-        total_qname_bytes_read_from_offset += 1;
+        read_pos += 1; // Advance past the length byte
+        if !pointer_seen {
+            consumed_from_start = read_pos - start_offset;
+        }
 
         if label_len_byte == 0 { // End of QNAME (null label)
             break;
@@ -388,19 +473,23 @@ fn parse_qname_from_dns_packet(packet_data: &[u8], start_offset: usize) -> Resul
         if label_len_byte > 63 { // Max label length in DNS
             return Err(format!("QNAME label too long: {} bytes (max 63)", label_len_byte));
         }
-        if current_pos_in_packet + (label_len_byte as usize) > packet_data.len() {
+        if read_pos + (label_len_byte as usize) > packet_data.len() {
             return Err("Buffer too short while reading QNAME label data".to_string());
         }
 
-        let label_bytes = &packet_data[current_pos_in_packet .. current_pos_in_packet + (label_len_byte as usize)];
+        let label_bytes = &packet_data[read_pos .. read_pos + (label_len_byte as usize)];
         let label_str = std::str::from_utf8(label_bytes)
             .map_err(|_| "QNAME label contains invalid UTF-8 characters".to_string())?;
         qname_parts.push(label_str.to_string());
 
-        current_pos_in_packet += label_len_byte as usize;
-        total_qname_bytes_read_from_offset += label_len_byte as usize;
+        read_pos += label_len_byte as usize;
+        if !pointer_seen {
+            consumed_from_start = read_pos - start_offset;
+        }
     }
-    
+
+    let total_qname_bytes_read_from_offset = consumed_from_start;
+
     // If qname_parts is empty, it means the QNAME was just a single null byte (e.g. for root "."),
     // otherwise, join parts with dots.
     if qname_parts.is_empty() && total_qname_bytes_read_from_offset == 1 { // Only a single 0x00 byte for root.
@@ -410,6 +499,54 @@ fn parse_qname_from_dns_packet(packet_data: &[u8], start_offset: usize) -> Resul
     }
 }
 
+/// Returns the elements of `items` in round-robin order starting at index `start % len`, so
+/// successive calls with an advancing `start` cycle through every element as the first one.
+/// Yields nothing for an empty slice.
+fn rotated<T>(items: &[T], start: usize) -> impl Iterator<Item = &T> {
+    let len = items.len();
+    let offset = if len == 0 { 0 } else { start % len };
+    items.iter().cycle().skip(offset).take(len)
+}
+
+/// Appends `name` to `buf` using DNS name compression (RFC 1035 4.1.4): if `name`, or a trailing
+/// suffix of it, was already written earlier in this packet, a 2-byte pointer (`0xC000 |
+/// offset`) replaces the repeated labels; any remaining leading labels are written literally and
+/// registered so later names can point back to them in turn.
+///
+/// `absolute_offset_of_buf_start` is where `buf`'s first byte lands in the final packet (e.g.
+/// 12 for a buffer that starts right after the header), since pointers are absolute offsets from
+/// the start of the message. Suffixes are only registered when their offset fits the pointer's
+/// 14-bit field (<= 0x3FFF); beyond that they're simply written out literally every time.
+fn write_compressed_name(
+    name: &str,
+    buf: &mut Vec<u8>,
+    absolute_offset_of_buf_start: usize,
+    compression_map: &mut HashMap<Vec<String>, u16>,
+) {
+    let labels: Vec<&str> = if name == "." || name.is_empty() {
+        Vec::new()
+    } else {
+        name.trim_end_matches('.').split('.').collect()
+    };
+
+    for start_index in 0..labels.len() {
+        // DNS names are case-insensitive, so suffixes are keyed in lowercase.
+        let suffix_key: Vec<String> = labels[start_index..].iter().map(|l| l.to_lowercase()).collect();
+        if let Some(&pointer_offset) = compression_map.get(&suffix_key) {
+            buf.extend_from_slice(&u16_to_bytes_be(0xC000 | pointer_offset));
+            return;
+        }
+        let current_absolute_offset = absolute_offset_of_buf_start + buf.len();
+        if current_absolute_offset <= 0x3FFF {
+            compression_map.insert(suffix_key, current_absolute_offset as u16);
+        }
+        let label = labels[start_index];
+        buf.push(label.len() as u8);
+        buf.extend_from_slice(label.as_bytes());
+    }
+    buf.push(0); // No suffix matched (or the name is the root): terminate with the root label.
+}
+
 /// Formats a domain name string (e.g., "host.example.com" or "my-tag")
 /// into the DNS label-sequence format (e.g., \x04host\x07example\x03com\x00).
 fn format_name_for_dns_packet(name: &str) -> Vec<u8> {
@@ -444,168 +581,506 @@ fn format_name_for_dns_packet(name: &str) -> Vec<u8> {
 // DNS Packet Parsing and Building Logic
 // ---------------
 
+/// Builds a bare `DnsQueryError` (no transaction id or question recovered) for a malformed
+/// packet. Used while we still don't have a readable header.
+fn formerr_without_context(message: String) -> DnsQueryError {
+    DnsQueryError { rcode: RCODE_FORMAT_ERROR, message, transaction_id: None, questions: Vec::new() }
+}
+
 /// Parses an incoming DNS query packet.
-fn parse_dns_query_packet(packet_bytes: &[u8]) -> Result<DnsQueryInfo, String> {
+fn parse_dns_query_packet(packet_bytes: &[u8]) -> Result<DnsQueryInfo, DnsQueryError> {
     if packet_bytes.len() < 12 { // DNS header is 12 bytes
-        return Err("DNS packet too short (less than 12 bytes for header)".to_string());
+        return Err(formerr_without_context("DNS packet too short (less than 12 bytes for header)".to_string()));
     }
 
-    let transaction_id = bytes_to_u16_be(&packet_bytes[0..2])?;
-    let flags = bytes_to_u16_be(&packet_bytes[2..4])?;
-    let qd_count = bytes_to_u16_be(&packet_bytes[4..6])?; // Question count
-    // ANCOUNT, NSCOUNT, ARCOUNT are at offsets 6, 8, 10 respectively. Ignored for parsing a query.
+    // From here on we can always recover at least the transaction id for the error response.
+    let transaction_id = bytes_to_u16_be(&packet_bytes[0..2])
+        .map_err(|e| formerr_without_context(e))?;
+    let flags = bytes_to_u16_be(&packet_bytes[2..4])
+        .map_err(|e| DnsQueryError { rcode: RCODE_FORMAT_ERROR, message: e, transaction_id: Some(transaction_id), questions: Vec::new() })?;
+    let qd_count = bytes_to_u16_be(&packet_bytes[4..6]) // Question count
+        .map_err(|e| DnsQueryError { rcode: RCODE_FORMAT_ERROR, message: e, transaction_id: Some(transaction_id), questions: Vec::new() })?;
+    // ANCOUNT and NSCOUNT (offsets 6, 8) are ignored for parsing a query; ARCOUNT (offset 10) is
+    // read below so we can look for an EDNS0 OPT record in the Additional section.
+    let ar_count = bytes_to_u16_be(&packet_bytes[10..12])
+        .map_err(|e| DnsQueryError { rcode: RCODE_FORMAT_ERROR, message: e, transaction_id: Some(transaction_id), questions: Vec::new() })?;
+
+    // Captures whatever questions have been successfully parsed so far, so a failure partway
+    // through a multi-question packet can still echo the questions it did manage to parse.
+    let fail = |rcode: u16, message: String, questions: &[DnsQuestion]| DnsQueryError {
+        rcode, message, transaction_id: Some(transaction_id), questions: questions.to_vec(),
+    };
 
     // QR bit (bit 15 of flags): 0 for query, 1 for response.
     if (flags & FLAG_QR_RESPONSE) != 0 { // 0x8000
-        return Err("Received packet is not a DNS query (QR bit is set to 1)".to_string());
+        return Err(fail(RCODE_FORMAT_ERROR, "Received packet is not a DNS query (QR bit is set to 1)".to_string(), &[]));
     }
     // Opcode (bits 14-11 of flags): Should be 0 for a standard query (QUERY).
     let opcode = (flags >> 11) & 0x0F;
     if opcode != 0 {
-        // We only support standard queries. Could respond with FORMERR.
-        return Err(format!("Unsupported DNS Opcode: {}. Only Opcode 0 (QUERY) is supported.", opcode));
+        // We only support standard queries.
+        return Err(fail(RCODE_NOT_IMPLEMENTED, format!("Unsupported DNS Opcode: {}. Only Opcode 0 (QUERY) is supported.", opcode), &[]));
     }
     if qd_count == 0 {
-        return Err("DNS query contains no questions (QDCOUNT is 0)".to_string());
-    }
-    if qd_count > 1 {
-        // This simple server only handles one question per query.
-        return Err("Multiple questions in a single DNS query are not supported.".to_string());
+        return Err(fail(RCODE_FORMAT_ERROR, "DNS query contains no questions (QDCOUNT is 0)".to_string(), &[]));
     }
 
     let mut current_offset_in_packet = 12; // Questions start after the 12-byte header
 
-    // Parse QNAME (the domain name being queried)
-    let (qname_str, qname_bytes_len) = parse_qname_from_dns_packet(packet_bytes, current_offset_in_packet)
-        .map_err(|e| format!("Failed to parse QNAME from DNS query: {}", e))?;
-    current_offset_in_packet += qname_bytes_len;
+    // Parse every question in the packet (almost always just one in practice, but QDCOUNT can
+    // legitimately be greater than 1).
+    let mut questions: Vec<DnsQuestion> = Vec::with_capacity(qd_count as usize);
+    for _ in 0..qd_count {
+        // Parse QNAME (the domain name being queried)
+        let (qname_str, qname_bytes_len) = parse_qname_from_dns_packet(packet_bytes, current_offset_in_packet)
+            .map_err(|e| fail(RCODE_FORMAT_ERROR, format!("Failed to parse QNAME from DNS query: {}", e), &questions))?;
+        current_offset_in_packet += qname_bytes_len;
+
+        // Ensure there's enough data left for QTYPE and QCLASS (2 bytes each)
+        if packet_bytes.len() < current_offset_in_packet + 4 {
+            return Err(fail(RCODE_FORMAT_ERROR, "DNS packet too short after QNAME (missing QTYPE/QCLASS)".to_string(), &questions));
+        }
+        let qtype = bytes_to_u16_be(&packet_bytes[current_offset_in_packet .. current_offset_in_packet + 2])
+            .map_err(|e| fail(RCODE_FORMAT_ERROR, e, &questions))?;
+        current_offset_in_packet += 2;
+        let qclass = bytes_to_u16_be(&packet_bytes[current_offset_in_packet .. current_offset_in_packet + 2])
+            .map_err(|e| fail(RCODE_FORMAT_ERROR, e, &questions))?;
+        current_offset_in_packet += 2;
+
+        if qclass != QCLASS_IN {
+            // We only support Internet class queries. The questions parsed so far (including
+            // this one) are echoed back.
+            questions.push(DnsQuestion { qname: qname_str, qtype, qclass });
+            return Err(fail(
+                RCODE_REFUSED,
+                format!("Unsupported DNS query class: {}. Only QCLASS IN (1) is supported.", qclass),
+                &questions,
+            ));
+        }
 
-    // Ensure there's enough data left for QTYPE and QCLASS (2 bytes each)
-    if packet_bytes.len() < current_offset_in_packet + 4 {
-        return Err("DNS packet too short after QNAME (missing QTYPE/QCLASS)".to_string());
+        questions.push(DnsQuestion { qname: qname_str, qtype, qclass });
     }
-    let qtype = bytes_to_u16_be(&packet_bytes[current_offset_in_packet .. current_offset_in_packet + 2])?;
-    current_offset_in_packet += 2;
-    let qclass = bytes_to_u16_be(&packet_bytes[current_offset_in_packet .. current_offset_in_packet + 2])?;
-    // current_offset_in_packet += 2; // Not needed for further parsing in this simple case
 
-    if qclass != QCLASS_IN {
-        // We only support Internet class queries.
-        return Err(format!("Unsupported DNS query class: {}. Only QCLASS IN (1) is supported.", qclass));
+    // Scan the Additional section for an EDNS0 OPT pseudo-record. This is best-effort: any
+    // record we don't recognize is skipped using its NAME/TYPE/CLASS/TTL/RDLENGTH header, and if
+    // the section turns out to be malformed we simply stop looking rather than failing the whole
+    // query (EDNS0 is an optional enhancement, not something we depend on to answer).
+    let mut edns_udp_payload_size: Option<u16> = None;
+    let mut additional_record_offset = current_offset_in_packet;
+    for _ in 0..ar_count {
+        let (_rr_name, rr_name_len) = match parse_qname_from_dns_packet(packet_bytes, additional_record_offset) {
+            Ok(result) => result,
+            Err(_) => break,
+        };
+        let mut rr_field_offset = additional_record_offset + rr_name_len;
+        if packet_bytes.len() < rr_field_offset + 10 {
+            break; // Not enough room for TYPE/CLASS/TTL/RDLENGTH
+        }
+        let rr_type = bytes_to_u16_be(&packet_bytes[rr_field_offset .. rr_field_offset + 2]).unwrap_or(0);
+        let rr_class = bytes_to_u16_be(&packet_bytes[rr_field_offset + 2 .. rr_field_offset + 4]).unwrap_or(0);
+        // TTL (4 bytes) packs the extended RCODE/version/flags for OPT; we don't need them here.
+        rr_field_offset += 8; // past TYPE, CLASS, TTL
+        let rdlength = bytes_to_u16_be(&packet_bytes[rr_field_offset .. rr_field_offset + 2]).unwrap_or(0) as usize;
+        rr_field_offset += 2;
+        if packet_bytes.len() < rr_field_offset + rdlength {
+            break;
+        }
+
+        if rr_type == TYPE_OPT {
+            // For OPT, NAME must be the root (0x00) and CLASS carries the requestor's UDP
+            // payload size.
+            edns_udp_payload_size = Some(rr_class);
+        }
+
+        additional_record_offset = rr_field_offset + rdlength;
     }
 
     Ok(DnsQueryInfo {
         transaction_id,
-        question: DnsQuestion {
-            qname: qname_str,
-            qtype,
-            qclass,
-        },
+        questions,
         // client_flags: flags, // Could store this if needed
+        edns_udp_payload_size,
     })
 }
 
+/// Builds a minimal DNS error response from whatever could be recovered from a query that
+/// failed to parse (or was rejected). Always produces a valid reply so the caller never has to
+/// drop the datagram: QR=1, ANCOUNT=0, NSCOUNT=0, ARCOUNT=0, and the given RCODE. Whichever
+/// questions were successfully parsed before the failure occurred are echoed back with QDCOUNT
+/// set accordingly; if none were, QDCOUNT=0. If even the transaction id could not be recovered,
+/// 0 is used.
+fn build_dns_error_response_packet(query_error: &DnsQueryError) -> Vec<u8> {
+    let mut response_bytes_vec: Vec<u8> = Vec::new();
+
+    let transaction_id = query_error.transaction_id.unwrap_or(0);
+    let qd_count = query_error.questions.len() as u16;
+
+    response_bytes_vec.extend_from_slice(&u16_to_bytes_be(transaction_id));
+    let response_flags = FLAG_QR_RESPONSE | FLAG_AA | (query_error.rcode & 0x000F);
+    response_bytes_vec.extend_from_slice(&u16_to_bytes_be(response_flags));
+    response_bytes_vec.extend_from_slice(&u16_to_bytes_be(qd_count));
+    response_bytes_vec.extend_from_slice(&u16_to_bytes_be(0)); // ANCOUNT
+    response_bytes_vec.extend_from_slice(&u16_to_bytes_be(0)); // NSCOUNT
+    response_bytes_vec.extend_from_slice(&u16_to_bytes_be(0)); // ARCOUNT
+
+    for question in &query_error.questions {
+        response_bytes_vec.extend_from_slice(&format_name_for_dns_packet(&question.qname));
+        response_bytes_vec.extend_from_slice(&u16_to_bytes_be(question.qtype));
+        response_bytes_vec.extend_from_slice(&u16_to_bytes_be(question.qclass));
+    }
+
+    response_bytes_vec
+}
+
+/// A resolvable DNS entry for a configured tag. A tag can have any number of IPv4 and/or IPv6
+/// addresses (e.g. several instances behind the same tag, or a dual-stack service) sharing the
+/// same port; each address is answered as its own RR. If the configured addresses disagree on
+/// the port, the first one registered for the tag wins (a warning is logged for the rest) rather
+/// than whichever address happens to be processed last.
+#[derive(Debug)]
+struct DnsRecordEntry {
+    v4_addresses: Vec<Ipv4Addr>,
+    v6_addresses: Vec<Ipv6Addr>,
+    port: u16,
+    tags: Vec<String>, // All tags this entry was registered under, exposed via TXT
+    // Advances on every query answered for this entry, so successive queries for a multi-homed
+    // tag start from a different address: cheap client-side load spreading across instances,
+    // matching how real authoritative servers round-robin multi-homed records.
+    rotation_counter: AtomicUsize,
+}
+
 /// Builds a DNS response packet based on the parsed query and configured data.
-fn build_dns_response_packet(
+///
+/// `max_udp_payload_size` is the size limit to enforce on the assembled packet: `Some(n)` for
+/// UDP transport (where it should be the EDNS0-negotiated size, or `DEFAULT_UDP_PAYLOAD_SIZE`
+/// when the client did not send EDNS0), or `None` for TCP transport, which is not subject to the
+/// classic 512-byte ceiling.
+///
+/// A dual-stack name queried on the family it doesn't have configured (A on an IPv6-only name,
+/// or vice versa) follows the same known-name-unsupported-type convention as any other RR type we
+/// don't serve for that name: NOERROR with ANCOUNT=0, so negative caching still applies.
+fn build_dns_response_packet_for_transport(
     query_info: &DnsQueryInfo,
-    // HashMap mapping: normalized_tag_string -> (IPv4Address, port_number)
-    dns_data_map: &HashMap<String, (Ipv4Addr, u16)>,
+    // HashMap mapping: normalized_tag_string -> DnsRecordEntry
+    dns_data_map: &HashMap<String, DnsRecordEntry>,
+    max_udp_payload_size: Option<u16>,
 ) -> Vec<u8> {
     let mut response_bytes_vec: Vec<u8> = Vec::new();
     let mut answer_record_count: u16 = 0;
-    let mut response_code = RCODE_NO_ERROR; // Assume success initially
+    let mut any_question_matched = false;
     let mut answer_section_payload_bytes: Vec<u8> = Vec::new();
+    let ttl_value: u32 = 60; // Time-To-Live for the record (e.g., 60 seconds)
+
+    // Tracks every domain-name suffix written so far and the absolute offset (from the start of
+    // the packet) it was first written at, so later names anywhere in the packet - answer
+    // owners, SRV/SOA names in RDATA - can point back to them instead of repeating the labels.
+    let mut compression_map: HashMap<Vec<String>, u16> = HashMap::new();
+
+    // Encode the question section first, since it's written first on the wire: this lets every
+    // later name (answer owners especially, which are almost always identical to the queried
+    // name) compress against it.
+    let mut question_section_payload_bytes: Vec<u8> = Vec::new();
+    for question in &query_info.questions {
+        write_compressed_name(&question.qname, &mut question_section_payload_bytes, 12, &mut compression_map);
+        question_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(question.qtype));
+        question_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(question.qclass));
+    }
+    let answer_section_absolute_offset = 12 + question_section_payload_bytes.len();
+
+    for question in &query_info.questions {
+        // Normalize the queried name for lookup in our map (lowercase, strip trailing dot)
+        let lookup_qname_key = question.qname
+            .strip_suffix('.') // DNS FQDNs often end with a dot
+            .unwrap_or(&question.qname)
+            .to_lowercase(); // DNS names are case-insensitive
+
+        let record_entry = match dns_data_map.get(&lookup_qname_key) {
+            Some(record_entry) => record_entry,
+            None => continue, // Name not found; contributes no answers for this question.
+        };
+        any_question_matched = true;
+        // Advance the rotation counter once per matched question, and use it as the starting
+        // offset into whichever address list this question ends up answering from.
+        let rotation_start = record_entry.rotation_counter.fetch_add(1, Ordering::Relaxed);
 
-    // Normalize the queried name for lookup in our map (lowercase, strip trailing dot)
-    let lookup_qname_key = query_info.question.qname
-        .strip_suffix('.') // DNS FQDNs often end with a dot
-        .unwrap_or(&query_info.question.qname)
-        .to_lowercase(); // DNS names are case-insensitive
-
-    if let Some((ip_address, port_number)) = dns_data_map.get(&lookup_qname_key) {
-        // Name found in our data. Now check QTYPE.
-        let qname_bytes_for_rr = format_name_for_dns_packet(&query_info.question.qname); // Use original QNAME from query for the RR
-        let ttl_value: u32 = 60; // Time-To-Live for the record (e.g., 60 seconds)
-
-        match query_info.question.qtype {
+        match question.qtype {
             QTYPE_A => {
-                answer_record_count = 1;
-                answer_section_payload_bytes.extend_from_slice(&qname_bytes_for_rr); // NAME
-                answer_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(QTYPE_A));  // TYPE
-                answer_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(QCLASS_IN)); // CLASS
-                answer_section_payload_bytes.extend_from_slice(&u32_to_bytes_be(ttl_value));   // TTL
-                answer_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(4));       // RDLENGTH (4 bytes for an IPv4 address)
-                answer_section_payload_bytes.extend_from_slice(&ip_address.octets());      // RDATA (the IP address bytes)
+                // One answer RR per configured IPv4 address for this name, starting from the
+                // rotated offset so repeated queries spread load across all of them.
+                for ip_address in rotated(&record_entry.v4_addresses, rotation_start) {
+                    answer_record_count += 1;
+                    write_compressed_name(&question.qname, &mut answer_section_payload_bytes, answer_section_absolute_offset, &mut compression_map); // NAME
+                    answer_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(QTYPE_A));  // TYPE
+                    answer_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(QCLASS_IN)); // CLASS
+                    answer_section_payload_bytes.extend_from_slice(&u32_to_bytes_be(ttl_value));   // TTL
+                    answer_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(4));       // RDLENGTH (4 bytes for an IPv4 address)
+                    answer_section_payload_bytes.extend_from_slice(&ip_address.octets());      // RDATA (the IP address bytes)
+                }
+                // If there are no IPv4 addresses configured, this contributes zero answers
+                // (NOERROR/ANCOUNT=0 for this question), so negative caching still works.
+            }
+            QTYPE_AAAA => {
+                // One answer RR per configured IPv6 address for this name, same rotation scheme.
+                for ip_address in rotated(&record_entry.v6_addresses, rotation_start) {
+                    answer_record_count += 1;
+                    write_compressed_name(&question.qname, &mut answer_section_payload_bytes, answer_section_absolute_offset, &mut compression_map); // NAME
+                    answer_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(QTYPE_AAAA)); // TYPE
+                    answer_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(QCLASS_IN)); // CLASS
+                    answer_section_payload_bytes.extend_from_slice(&u32_to_bytes_be(ttl_value));   // TTL
+                    answer_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(16));      // RDLENGTH (16 bytes for an IPv6 address)
+                    answer_section_payload_bytes.extend_from_slice(&ip_address.octets());      // RDATA (the IP address bytes)
+                }
             }
             QTYPE_TXT => {
-                let txt_record_data_string = format!("{}:{}", ip_address, port_number);
-                // A single character-string in a TXT RDATA can be max 255 bytes long.
-                if txt_record_data_string.len() > 255 {
-                    // If data is too long, we can't form a valid single-string TXT record.
-                    // A more complex server might split it into multiple character-strings.
-                    // For simplicity, we'll respond as if the type isn't implemented or an error.
-                    answer_record_count = 0;
-                    response_code = RCODE_SERVER_FAILURE; // Or RCODE_NOT_IMPLEMENTED
-                } else {
-                    answer_record_count = 1;
-                    answer_section_payload_bytes.extend_from_slice(&qname_bytes_for_rr);    // NAME
-                    answer_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(QTYPE_TXT)); // TYPE
-                    answer_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(QCLASS_IN));// CLASS
-                    answer_section_payload_bytes.extend_from_slice(&u32_to_bytes_be(ttl_value));  // TTL
-                    
-                    let txt_payload_as_bytes = txt_record_data_string.as_bytes();
-                    // RDATA for TXT: one or more <character-string>, where <character-string> is <1_byte_length><characters>
-                    let rdata_length_for_txt = 1 + txt_payload_as_bytes.len(); // 1 byte for string length + string bytes
-                    answer_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(rdata_length_for_txt as u16)); // RDLENGTH
-                    answer_section_payload_bytes.push(txt_payload_as_bytes.len() as u8); // The <1_byte_length>
-                    answer_section_payload_bytes.extend_from_slice(txt_payload_as_bytes);   // The <characters>
+                // Expose the service metadata that doesn't fit in an A/AAAA record: the
+                // configured port, plus every tag this entry is known under. Each becomes its
+                // own attribute string in the TXT RDATA; attributes longer than 255 bytes are
+                // split across consecutive <character-string>s (the wire format allows any number
+                // of them back-to-back in one TXT RR, and resolvers are expected to concatenate
+                // same-RR character-strings belonging to one logical attribute).
+                let mut txt_attributes: Vec<String> = Vec::with_capacity(1 + record_entry.tags.len());
+                txt_attributes.push(format!("port={}", record_entry.port));
+                for tag in &record_entry.tags {
+                    txt_attributes.push(tag.clone());
+                }
+
+                // A single character-string can carry at most 255 bytes, so chunk each attribute
+                // into 255-byte pieces before framing it with its length byte.
+                let txt_character_strings: Vec<&[u8]> = txt_attributes
+                    .iter()
+                    .flat_map(|attribute| attribute.as_bytes().chunks(255))
+                    .collect();
+
+                answer_record_count += 1;
+                write_compressed_name(&question.qname, &mut answer_section_payload_bytes, answer_section_absolute_offset, &mut compression_map); // NAME
+                answer_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(QTYPE_TXT)); // TYPE
+                answer_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(QCLASS_IN));// CLASS
+                answer_section_payload_bytes.extend_from_slice(&u32_to_bytes_be(ttl_value));  // TTL
+
+                // RDATA for TXT: one or more <character-string>, where <character-string> is <1_byte_length><characters>
+                let rdata_length_for_txt: usize = txt_character_strings.iter().map(|s| 1 + s.len()).sum();
+                answer_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(rdata_length_for_txt as u16)); // RDLENGTH
+                for character_string in &txt_character_strings {
+                    answer_section_payload_bytes.push(character_string.len() as u8); // The <1_byte_length>
+                    answer_section_payload_bytes.extend_from_slice(character_string);  // The <characters>
                 }
             }
+            QTYPE_SRV => {
+                // SRV RDATA: 2-byte priority, 2-byte weight, 2-byte port, then the target name.
+                // The config doesn't carry priority/weight, so both default to 0. The target is
+                // the queried tag itself, since that's what our A/AAAA records resolve.
+                let srv_priority: u16 = 0;
+                let srv_weight: u16 = 0;
+
+                answer_record_count += 1;
+                write_compressed_name(&question.qname, &mut answer_section_payload_bytes, answer_section_absolute_offset, &mut compression_map); // NAME
+                answer_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(QTYPE_SRV)); // TYPE
+                answer_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(QCLASS_IN)); // CLASS
+                answer_section_payload_bytes.extend_from_slice(&u32_to_bytes_be(ttl_value));   // TTL
+
+                // The target name's compressed bytes depend on the dictionary state at the point
+                // it's written, which is after RDLENGTH and the priority/weight/port fields -
+                // build it into a scratch buffer first so RDLENGTH can be computed correctly.
+                let target_absolute_offset = answer_section_absolute_offset + answer_section_payload_bytes.len() + 2 + 6;
+                let mut target_name_bytes: Vec<u8> = Vec::new();
+                write_compressed_name(&question.qname, &mut target_name_bytes, target_absolute_offset, &mut compression_map);
+
+                answer_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(6 + target_name_bytes.len() as u16)); // RDLENGTH
+                answer_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(srv_priority));
+                answer_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(srv_weight));
+                answer_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(record_entry.port));
+                answer_section_payload_bytes.extend_from_slice(&target_name_bytes);
+            }
             _ => {
-                // Queried name exists, but for a type we don't serve (e.g., AAAA, MX).
+                // Queried name exists, but for a type we don't serve (e.g., MX).
                 // RFC 2308 (sec 2.2, 7.1) suggests responding with NOERROR and ANCOUNT=0 for known names but unsupported types.
-                answer_record_count = 0;
-                response_code = RCODE_NO_ERROR; // Not RCODE_NOT_IMPLEMENTED, to allow negative caching for this QNAME/QTYPE.
             }
         }
+    }
+
+    // RCODE is per-message, not per-question. NXDOMAIN only applies when none of the questions
+    // matched a configured name.
+    let response_code = if any_question_matched {
+        RCODE_NO_ERROR
     } else {
-        // Name not found in our configured data.
-        response_code = RCODE_NXDOMAIN;
+        RCODE_NXDOMAIN
+    };
+
+    // For NXDOMAIN, and for NOERROR responses that carry no answers (known name, unsupported
+    // type), include our SOA in the Authority section so resolvers can derive a negative-cache
+    // TTL from the MINIMUM field (RFC 2308).
+    let authority_section_absolute_offset = answer_section_absolute_offset + answer_section_payload_bytes.len();
+    let mut authority_section_payload_bytes: Vec<u8> = Vec::new();
+    let mut authority_record_count: u16 = 0;
+    if answer_record_count == 0 && (response_code == RCODE_NXDOMAIN || response_code == RCODE_NO_ERROR) {
+        authority_record_count = 1;
+        write_compressed_name(ZONE_APEX, &mut authority_section_payload_bytes, authority_section_absolute_offset, &mut compression_map); // NAME
+        authority_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(TYPE_SOA)); // TYPE
+        authority_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(QCLASS_IN)); // CLASS
+        authority_section_payload_bytes.extend_from_slice(&u32_to_bytes_be(SOA_MINIMUM_TTL)); // TTL
+
+        // MNAME/RNAME are written into a scratch buffer first (same reasoning as the SRV
+        // target above) so RDLENGTH can be computed before the RDATA itself is emitted.
+        let rdata_absolute_offset = authority_section_absolute_offset + authority_section_payload_bytes.len() + 2;
+        let mut soa_names_bytes: Vec<u8> = Vec::new();
+        write_compressed_name(SOA_MNAME, &mut soa_names_bytes, rdata_absolute_offset, &mut compression_map);
+        write_compressed_name(SOA_RNAME, &mut soa_names_bytes, rdata_absolute_offset, &mut compression_map);
+
+        authority_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(soa_names_bytes.len() as u16 + 20)); // RDLENGTH (5 u32 fields = 20 bytes)
+        authority_section_payload_bytes.extend_from_slice(&soa_names_bytes);
+        authority_section_payload_bytes.extend_from_slice(&u32_to_bytes_be(SOA_SERIAL));
+        authority_section_payload_bytes.extend_from_slice(&u32_to_bytes_be(SOA_REFRESH));
+        authority_section_payload_bytes.extend_from_slice(&u32_to_bytes_be(SOA_RETRY));
+        authority_section_payload_bytes.extend_from_slice(&u32_to_bytes_be(SOA_EXPIRE));
+        authority_section_payload_bytes.extend_from_slice(&u32_to_bytes_be(SOA_MINIMUM_TTL));
+    }
+
+    // If the client advertised EDNS0 support, mirror it back with our own OPT record in the
+    // Additional section so the conversation stays EDNS0-capable (and larger responses become
+    // possible).
+    let mut additional_section_payload_bytes: Vec<u8> = Vec::new();
+    let mut additional_record_count: u16 = 0;
+    if query_info.edns_udp_payload_size.is_some() {
+        additional_record_count = 1;
+        additional_section_payload_bytes.push(0); // NAME: root
+        additional_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(TYPE_OPT)); // TYPE
+        additional_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(OUR_MAX_UDP_PAYLOAD_SIZE)); // CLASS: our UDP payload size
+        additional_section_payload_bytes.extend_from_slice(&u32_to_bytes_be(0)); // TTL: extended RCODE/version/flags, all zero
+        additional_section_payload_bytes.extend_from_slice(&u16_to_bytes_be(0)); // RDLENGTH: no options
+    }
+
+    // Enforce the negotiated size limit on UDP. If the assembled answer section would push the
+    // packet over it, drop the answers (keeping the questions and our OPT record) and signal
+    // truncation via the TC bit so the client knows to retry over TCP.
+    let mut truncated = false;
+    if let Some(negotiated_size) = max_udp_payload_size {
+        let packet_size_with_answers = 12
+            + question_section_payload_bytes.len()
+            + answer_section_payload_bytes.len()
+            + authority_section_payload_bytes.len()
+            + additional_section_payload_bytes.len();
+        if packet_size_with_answers > negotiated_size as usize {
+            answer_section_payload_bytes.clear();
+            answer_record_count = 0;
+            truncated = true;
+        }
     }
 
     // Construct the DNS Header (12 bytes)
     response_bytes_vec.extend_from_slice(&u16_to_bytes_be(query_info.transaction_id)); // Transaction ID
-    let response_flags = FLAG_QR_RESPONSE | FLAG_AA | (response_code & 0x000F); // QR=1, AA=1, RCODE
+    let mut response_flags = FLAG_QR_RESPONSE | FLAG_AA | (response_code & 0x000F); // QR=1, AA=1, RCODE
+    if truncated {
+        response_flags |= FLAG_TC;
+    }
     response_bytes_vec.extend_from_slice(&u16_to_bytes_be(response_flags));
-    response_bytes_vec.extend_from_slice(&u16_to_bytes_be(1)); // QDCOUNT (Question Count) = 1 (echoing the question)
+    response_bytes_vec.extend_from_slice(&u16_to_bytes_be(query_info.questions.len() as u16)); // QDCOUNT (echoing all questions)
     response_bytes_vec.extend_from_slice(&u16_to_bytes_be(answer_record_count)); // ANCOUNT (Answer Record Count)
-    response_bytes_vec.extend_from_slice(&u16_to_bytes_be(0)); // NSCOUNT (Authority Record Count) = 0
-    response_bytes_vec.extend_from_slice(&u16_to_bytes_be(0)); // ARCOUNT (Additional Record Count) = 0
+    response_bytes_vec.extend_from_slice(&u16_to_bytes_be(authority_record_count)); // NSCOUNT (Authority Record Count)
+    response_bytes_vec.extend_from_slice(&u16_to_bytes_be(additional_record_count)); // ARCOUNT (Additional Record Count)
 
     // Append Question Section (echoed from the query)
-    let qname_bytes_original_query = format_name_for_dns_packet(&query_info.question.qname);
-    response_bytes_vec.extend_from_slice(&qname_bytes_original_query);
-    response_bytes_vec.extend_from_slice(&u16_to_bytes_be(query_info.question.qtype));
-    response_bytes_vec.extend_from_slice(&u16_to_bytes_be(query_info.question.qclass));
+    response_bytes_vec.extend(question_section_payload_bytes);
 
-    // Append Answer Section (if any records were added)
+    // Append Answer Section (if any records were added, and it wasn't truncated away)
     response_bytes_vec.extend(answer_section_payload_bytes);
 
+    // Append Authority Section (our SOA record, for NXDOMAIN/NODATA negative caching)
+    response_bytes_vec.extend(authority_section_payload_bytes);
+
+    // Append Additional Section (our EDNS0 OPT record, if the client sent one)
+    response_bytes_vec.extend(additional_section_payload_bytes);
+
     response_bytes_vec // Return the complete response packet
 }
 
+/// Builds a DNS response for the UDP transport, enforcing the classic 512-byte limit (or the
+/// EDNS0-negotiated size when the client advertised one).
+fn build_dns_response_packet(
+    query_info: &DnsQueryInfo,
+    dns_data_map: &HashMap<String, DnsRecordEntry>,
+) -> Vec<u8> {
+    let negotiated_size = query_info
+        .edns_udp_payload_size
+        .map(|client_size| client_size.min(OUR_MAX_UDP_PAYLOAD_SIZE))
+        .unwrap_or(DEFAULT_UDP_PAYLOAD_SIZE);
+    build_dns_response_packet_for_transport(query_info, dns_data_map, Some(negotiated_size))
+}
+
+/// Builds a DNS response for the TCP transport, which is not subject to the UDP size ceiling.
+fn build_dns_response_packet_tcp(
+    query_info: &DnsQueryInfo,
+    dns_data_map: &HashMap<String, DnsRecordEntry>,
+) -> Vec<u8> {
+    build_dns_response_packet_for_transport(query_info, dns_data_map, None)
+}
+
 
 // ---------------
 // DNS Server Logic
 // ---------------
 
+/// Handles a single TCP DNS connection: DNS-over-TCP frames every message with a 2-byte
+/// big-endian length prefix (RFC 1035 4.2.2), so a client whose UDP answer came back truncated
+/// (TC bit set) can reconnect here to get the full, untruncated answer set.
+fn handle_tcp_dns_connection(mut stream: TcpStream, dns_records_map: &HashMap<String, DnsRecordEntry>) {
+    loop {
+        let mut length_prefix_bytes = [0u8; 2];
+        if stream.read_exact(&mut length_prefix_bytes).is_err() {
+            return; // Connection closed or errored; nothing more to do.
+        }
+        let message_length = u16::from_be_bytes(length_prefix_bytes) as usize;
+
+        let mut query_bytes = vec![0u8; message_length];
+        if stream.read_exact(&mut query_bytes).is_err() {
+            return;
+        }
+
+        let response_bytes = match parse_dns_query_packet(&query_bytes) {
+            Ok(parsed_dns_query) => build_dns_response_packet_tcp(&parsed_dns_query, dns_records_map),
+            Err(query_error) => {
+                eprintln!("Error parsing DNS query over TCP: {} (RCODE {})", query_error.message, query_error.rcode);
+                build_dns_error_response_packet(&query_error)
+            }
+        };
+
+        let response_length_prefix = u16_to_bytes_be(response_bytes.len() as u16);
+        if stream.write_all(&response_length_prefix).is_err() {
+            return;
+        }
+        if stream.write_all(&response_bytes).is_err() {
+            return;
+        }
+    }
+}
+
+/// Starts the TCP DNS listener, spawning a thread per connection so a slow or idle client
+/// cannot block other queries.
+fn start_tcp_dns_server(dns_records_map: Arc<HashMap<String, DnsRecordEntry>>) -> std::io::Result<()> {
+    let listen_address = "0.0.0.0:53";
+    let tcp_listener = TcpListener::bind(listen_address)?;
+    println!("DNS server listening on {} (TCP)", listen_address);
+
+    for incoming_stream in tcp_listener.incoming() {
+        match incoming_stream {
+            Ok(stream) => {
+                let dns_records_map = Arc::clone(&dns_records_map);
+                thread::spawn(move || handle_tcp_dns_connection(stream, &dns_records_map));
+            }
+            Err(e) => eprintln!("Error accepting TCP DNS connection: {}", e),
+        }
+    }
+    Ok(())
+}
+
 /// Starts the UDP DNS server.
 fn start_dns_server(config_data_from_protobuf: Vec<ExtractedInfo>) -> std::io::Result<()> {
-    // Prepare a map for efficient DNS record lookup: tag_string -> (IpAddr, Port)
-    let mut dns_records_map: HashMap<String, (Ipv4Addr, u16)> = HashMap::new();
+    // Prepare a map for efficient DNS record lookup: tag_string -> DnsRecordEntry
+    let mut dns_records_map: HashMap<String, DnsRecordEntry> = HashMap::new();
 
     for config_item in config_data_from_protobuf {
-        let ip_addr_obj = match config_item.ip.parse::<Ipv4Addr>() {
+        // `Uri.ip` can be a dotted-quad IPv4 address or a textual IPv6 address; parse it as
+        // whichever `IpAddr` variant it is rather than assuming IPv4.
+        let ip_addr_obj = match config_item.ip.parse::<IpAddr>() {
             Ok(ip) => ip,
             Err(e) => {
                 eprintln!(
@@ -623,15 +1098,43 @@ fn start_dns_server(config_data_from_protobuf: Vec<ExtractedInfo>) -> std::io::R
                 .strip_suffix('.')
                 .unwrap_or(&tag_string_from_config)
                 .to_lowercase();
-            
-            if dns_records_map.contains_key(&normalized_dns_key) {
-                // Log a warning if a tag is being redefined. The last definition will take precedence.
-                println!(
-                    "Warning: DNS tag '{}' is being redefined. The latest configuration for this tag will be used.",
-                    normalized_dns_key
-                );
+
+            // The TXT `port=` attribute and SRV RDATA both carry one port for the whole tag, so
+            // warn (rather than silently collapsing to whichever address is processed last) if
+            // two addresses configured under the same tag disagree on it. The first-configured
+            // port deterministically wins; it is never overwritten by a later, conflicting one.
+            if let Some(existing_entry) = dns_records_map.get(&normalized_dns_key) {
+                if existing_entry.port != port_number_u16 {
+                    println!(
+                        "Warning: DNS tag '{}' has conflicting ports ({} vs {}) across its addresses; \
+                         TXT/SRV will report the first-configured port ({}) for every address under this tag.",
+                        normalized_dns_key, existing_entry.port, port_number_u16, existing_entry.port
+                    );
+                }
+            }
+
+            let record_entry = dns_records_map.entry(normalized_dns_key.clone()).or_insert(DnsRecordEntry {
+                v4_addresses: Vec::new(),
+                v6_addresses: Vec::new(),
+                port: port_number_u16,
+                tags: Vec::new(),
+                rotation_counter: AtomicUsize::new(0),
+            });
+            if !record_entry.tags.contains(&tag_string_from_config) {
+                record_entry.tags.push(tag_string_from_config.clone());
+            }
+            match ip_addr_obj {
+                IpAddr::V4(v4) => {
+                    if !record_entry.v4_addresses.contains(&v4) {
+                        record_entry.v4_addresses.push(v4);
+                    }
+                }
+                IpAddr::V6(v6) => {
+                    if !record_entry.v6_addresses.contains(&v6) {
+                        record_entry.v6_addresses.push(v6);
+                    }
+                }
             }
-            dns_records_map.insert(normalized_dns_key, (ip_addr_obj, port_number_u16));
         }
     }
 
@@ -639,32 +1142,46 @@ fn start_dns_server(config_data_from_protobuf: Vec<ExtractedInfo>) -> std::io::R
         println!("No valid DNS records configured after processing the protobuf file. The DNS server will start but resolve no names.");
     } else {
         println!("DNS server will serve the following records:");
-        for (tag_key, (ip_val, port_val)) in &dns_records_map {
-            println!("  Tag: '{}' -> A: {}, TXT: {}:{}", tag_key, ip_val, ip_val, port_val);
+        for (tag_key, record_entry) in &dns_records_map {
+            println!(
+                "  Tag: '{}' -> A: {:?}, AAAA: {:?}, port: {}",
+                tag_key, record_entry.v4_addresses, record_entry.v6_addresses, record_entry.port
+            );
         }
     }
 
+    let dns_records_map = Arc::new(dns_records_map);
+
+    // Run the TCP listener concurrently: a UDP response that comes back truncated (TC bit set)
+    // sends clients here to fetch the full answer set.
+    let tcp_dns_records_map = Arc::clone(&dns_records_map);
+    thread::spawn(move || {
+        if let Err(e) = start_tcp_dns_server(tcp_dns_records_map) {
+            eprintln!("TCP DNS server encountered a fatal error: {}", e);
+        }
+    });
+
     let listen_address = "0.0.0.0:53"; // Listen on all interfaces, UDP port 53
     let udp_socket = UdpSocket::bind(listen_address)?; // This may require root/administrator privileges
-    println!("DNS server listening on {}", listen_address);
+    println!("DNS server listening on {} (UDP)", listen_address);
 
-    // Buffer for receiving incoming UDP packets. DNS over UDP is typically limited to 512 bytes
-    // unless EDNS is used (which this server does not implement).
-    let mut incoming_packet_buffer = [0u8; 512];
+    // Buffer for receiving incoming UDP packets, sized for our own max EDNS0 payload so large
+    // EDNS0-bearing queries aren't truncated on the way in.
+    let mut incoming_packet_buffer = vec![0u8; OUR_MAX_UDP_PAYLOAD_SIZE as usize];
 
     loop { // Main server loop: receive query, process, send response
         match udp_socket.recv_from(&mut incoming_packet_buffer) {
             Ok((number_of_bytes_received, client_source_address)) => {
                 let received_dns_packet_slice = &incoming_packet_buffer[..number_of_bytes_received];
-                
+
                 // Uncomment for verbose logging of raw packets:
                 // println!("Received DNS packet from {}: {:02X?}", client_source_address, received_dns_packet_slice);
 
                 match parse_dns_query_packet(received_dns_packet_slice) {
                     Ok(parsed_dns_query) => {
                         // Uncomment for verbose logging of parsed queries:
-                        // println!("Parsed DNS query from {}: {:?}", client_source_address, parsed_dns_query.question);
-                        
+                        // println!("Parsed DNS query from {}: {:?}", client_source_address, parsed_dns_query.questions);
+
                         let response_packet_bytes = build_dns_response_packet(&parsed_dns_query, &dns_records_map);
                         
                         // Uncomment for verbose logging of raw response packets:
@@ -674,13 +1191,18 @@ fn start_dns_server(config_data_from_protobuf: Vec<ExtractedInfo>) -> std::io::R
                             eprintln!("Error sending DNS response to {}: {}", client_source_address, e);
                         }
                     }
-                    Err(e) => {
-                        // Log errors during query parsing. A more robust server might try to send
-                        // a FORMERR DNS response if it can at least parse the Transaction ID.
+                    Err(query_error) => {
+                        // Even a malformed/unsupported query gets a reply: a conformant server
+                        // never silently drops a datagram it received.
                         eprintln!(
-                            "Error parsing DNS query from {}: {}. Raw packet: {:02X?}",
-                            client_source_address, e, received_dns_packet_slice
+                            "Error parsing DNS query from {}: {} (RCODE {}). Raw packet: {:02X?}",
+                            client_source_address, query_error.message, query_error.rcode, received_dns_packet_slice
                         );
+
+                        let error_response_bytes = build_dns_error_response_packet(&query_error);
+                        if let Err(e) = udp_socket.send_to(&error_response_bytes, client_source_address) {
+                            eprintln!("Error sending DNS error response to {}: {}", client_source_address, e);
+                        }
                     }
                 }
             }
@@ -767,3 +1289,361 @@ pub fn main() {
         std::process::exit(1); // Exit on critical server error
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a label-sequence QNAME (e.g. "a.bc" -> `\x01a\x02bc\x00`) with no compression.
+    fn encode_plain_name(name: &str) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for label in name.split('.') {
+            bytes.push(label.len() as u8);
+            bytes.extend_from_slice(label.as_bytes());
+        }
+        bytes.push(0);
+        bytes
+    }
+
+    #[test]
+    fn parses_a_plain_name_with_no_compression() {
+        let packet = encode_plain_name("www.example.com");
+        let (name, consumed) = parse_qname_from_dns_packet(&packet, 0).expect("should parse");
+        assert_eq!(name, "www.example.com");
+        assert_eq!(consumed, packet.len());
+    }
+
+    #[test]
+    fn parses_the_root_name() {
+        let packet = vec![0u8];
+        let (name, consumed) = parse_qname_from_dns_packet(&packet, 0).expect("should parse");
+        assert_eq!(name, ".");
+        assert_eq!(consumed, 1);
+    }
+
+    #[test]
+    fn follows_a_single_backwards_pointer() {
+        // Offset 0: "example.com\0". Offset after that: a QNAME that's just a pointer back to 0.
+        let mut packet = encode_plain_name("example.com");
+        let pointer_offset = packet.len();
+        packet.push(0xC0);
+        packet.push(0x00); // Pointer to offset 0
+        let (name, consumed) = parse_qname_from_dns_packet(&packet, pointer_offset).expect("should parse");
+        assert_eq!(name, "example.com");
+        // A pointer is exactly 2 bytes for length-accounting purposes, however far it jumps.
+        assert_eq!(consumed, 2);
+    }
+
+    #[test]
+    fn rejects_a_pointer_that_points_at_itself() {
+        // A 2-byte pointer at offset 0 pointing back to offset 0: not strictly backwards.
+        let packet = vec![0xC0, 0x00];
+        let result = parse_qname_from_dns_packet(&packet, 0);
+        assert!(result.is_err(), "a self-referential pointer must be rejected, not followed");
+    }
+
+    #[test]
+    fn rejects_a_forward_pointing_pointer() {
+        // Pointer at offset 0 claims to point to offset 4, which is ahead of it.
+        let mut packet = vec![0xC0, 0x04];
+        packet.extend_from_slice(&encode_plain_name("example.com"));
+        let result = parse_qname_from_dns_packet(&packet, 0);
+        assert!(result.is_err(), "a forward-pointing pointer must be rejected, not followed");
+    }
+
+    #[test]
+    fn rejects_a_chain_of_pointers_longer_than_the_jump_cap() {
+        // Each two-byte pointer at offset `1 + 2*i` points one slot further back to `1 + 2*(i-1)`
+        // (the first one to the root label at offset 0), forming a chain
+        // `MAX_QNAME_POINTER_JUMPS + 1` long that ends at a root label - every jump is
+        // individually valid (strictly backwards), so only the jump-count cap can catch this,
+        // which is exactly the anti-DoS property under test.
+        let chain_len = (MAX_QNAME_POINTER_JUMPS + 1) as usize;
+        let mut packet = vec![0u8]; // Root label at offset 0, the eventual target of the chain.
+        for i in 0..chain_len {
+            let target_offset = if i == 0 { 0 } else { (1 + (i - 1) * 2) as u16 };
+            packet.push(0xC0 | ((target_offset >> 8) as u8));
+            packet.push((target_offset & 0xFF) as u8);
+        }
+        let start_offset = packet.len() - 2;
+        let result = parse_qname_from_dns_packet(&packet, start_offset);
+        assert!(result.is_err(), "a pointer chain beyond the cap must be rejected, not followed to completion");
+    }
+
+    #[test]
+    fn rejects_a_truncated_packet() {
+        let packet = vec![5u8, b'h', b'e']; // Label claims 5 bytes but only 2 follow.
+        let result = parse_qname_from_dns_packet(&packet, 0);
+        assert!(result.is_err());
+    }
+
+    // ---------------
+    // Response encoding: build_dns_response_packet_for_transport / write_compressed_name
+    // ---------------
+
+    fn single_question_query(qname: &str, qtype: u16, edns_udp_payload_size: Option<u16>) -> DnsQueryInfo {
+        DnsQueryInfo {
+            transaction_id: 0xBEEF,
+            questions: vec![DnsQuestion { qname: qname.to_string(), qtype, qclass: QCLASS_IN }],
+            edns_udp_payload_size,
+        }
+    }
+
+    fn entry_with(v4_addresses: Vec<Ipv4Addr>, v6_addresses: Vec<Ipv6Addr>, port: u16, tags: Vec<&str>) -> DnsRecordEntry {
+        DnsRecordEntry {
+            v4_addresses,
+            v6_addresses,
+            port,
+            tags: tags.into_iter().map(String::from).collect(),
+            rotation_counter: AtomicUsize::new(0),
+        }
+    }
+
+    /// Reads the 12-byte header into (flags, qdcount, ancount, nscount, arcount).
+    fn read_header(packet: &[u8]) -> (u16, u16, u16, u16, u16) {
+        (
+            bytes_to_u16_be(&packet[2..4]).unwrap(),
+            bytes_to_u16_be(&packet[4..6]).unwrap(),
+            bytes_to_u16_be(&packet[6..8]).unwrap(),
+            bytes_to_u16_be(&packet[8..10]).unwrap(),
+            bytes_to_u16_be(&packet[10..12]).unwrap(),
+        )
+    }
+
+    #[test]
+    fn a_record_answer_owner_name_compresses_against_the_question() {
+        let query = single_question_query("foo.demo-service.internal", QTYPE_A, None);
+        let mut dns_data_map = HashMap::new();
+        dns_data_map.insert(
+            "foo.demo-service.internal".to_string(),
+            entry_with(vec![Ipv4Addr::new(10, 0, 0, 1)], vec![], 8080, vec!["foo.demo-service.internal"]),
+        );
+
+        let packet = build_dns_response_packet_for_transport(&query, &dns_data_map, None);
+
+        let (flags, qdcount, ancount, _nscount, _arcount) = read_header(&packet);
+        assert_eq!(flags & 0x000F, RCODE_NO_ERROR);
+        assert_eq!(qdcount, 1);
+        assert_eq!(ancount, 1);
+
+        let (_qname, qname_len) = parse_qname_from_dns_packet(&packet, 12).expect("question name parses");
+        let answer_offset = 12 + qname_len + 4; // past QTYPE/QCLASS
+
+        // The owner name is identical to the question, so it must compress down to a single
+        // 2-byte pointer back to offset 12 (right after the header), not be repeated literally.
+        assert_eq!(&packet[answer_offset..answer_offset + 2], &[0xC0, 0x0C]);
+        let (owner_name, owner_len) = parse_qname_from_dns_packet(&packet, answer_offset).expect("owner name parses");
+        assert_eq!(owner_name, "foo.demo-service.internal");
+        assert_eq!(owner_len, 2);
+
+        let mut offset = answer_offset + owner_len;
+        assert_eq!(bytes_to_u16_be(&packet[offset..offset + 2]).unwrap(), QTYPE_A);
+        offset += 2;
+        assert_eq!(bytes_to_u16_be(&packet[offset..offset + 2]).unwrap(), QCLASS_IN);
+        offset += 2;
+        offset += 4; // TTL
+        let rdlength = bytes_to_u16_be(&packet[offset..offset + 2]).unwrap();
+        offset += 2;
+        assert_eq!(rdlength, 4);
+        assert_eq!(&packet[offset..offset + 4], &[10, 0, 0, 1]);
+        assert_eq!(offset + 4, packet.len());
+    }
+
+    #[test]
+    fn srv_record_rdlength_matches_the_priority_weight_port_and_compressed_target() {
+        let query = single_question_query("svc.demo-service.internal", QTYPE_SRV, None);
+        let mut dns_data_map = HashMap::new();
+        dns_data_map.insert(
+            "svc.demo-service.internal".to_string(),
+            entry_with(vec![Ipv4Addr::new(10, 0, 0, 2)], vec![], 9090, vec!["svc.demo-service.internal"]),
+        );
+
+        let packet = build_dns_response_packet_for_transport(&query, &dns_data_map, None);
+
+        let (_qname, qname_len) = parse_qname_from_dns_packet(&packet, 12).expect("question name parses");
+        let answer_offset = 12 + qname_len + 4;
+        let (_owner_name, owner_len) = parse_qname_from_dns_packet(&packet, answer_offset).expect("owner name parses");
+
+        let mut offset = answer_offset + owner_len;
+        assert_eq!(bytes_to_u16_be(&packet[offset..offset + 2]).unwrap(), QTYPE_SRV);
+        offset += 8; // TYPE, CLASS, TTL
+        let rdlength = bytes_to_u16_be(&packet[offset..offset + 2]).unwrap() as usize;
+        offset += 2;
+        let rdata_start = offset;
+
+        assert_eq!(bytes_to_u16_be(&packet[offset..offset + 2]).unwrap(), 0); // priority
+        offset += 2;
+        assert_eq!(bytes_to_u16_be(&packet[offset..offset + 2]).unwrap(), 0); // weight
+        offset += 2;
+        assert_eq!(bytes_to_u16_be(&packet[offset..offset + 2]).unwrap(), 9090); // port
+        offset += 2;
+
+        let (target_name, target_len) = parse_qname_from_dns_packet(&packet, offset).expect("SRV target parses");
+        assert_eq!(target_name, "svc.demo-service.internal");
+        // The target is the same name as the owner/question, so it too compresses to a pointer.
+        assert_eq!(target_len, 2);
+
+        // RDLENGTH must account for exactly what was written: 6 fixed bytes plus the target name.
+        assert_eq!(rdlength, 6 + target_len);
+        assert_eq!(rdata_start + rdlength, packet.len());
+    }
+
+    #[test]
+    fn soa_authority_record_rdlength_matches_the_written_names_and_fixed_fields() {
+        let query = single_question_query("nonexistent.example", QTYPE_A, None);
+        let dns_data_map: HashMap<String, DnsRecordEntry> = HashMap::new();
+
+        let packet = build_dns_response_packet_for_transport(&query, &dns_data_map, None);
+
+        let (flags, _qdcount, ancount, nscount, _arcount) = read_header(&packet);
+        assert_eq!(flags & 0x000F, RCODE_NXDOMAIN);
+        assert_eq!(ancount, 0);
+        assert_eq!(nscount, 1);
+
+        let (_qname, qname_len) = parse_qname_from_dns_packet(&packet, 12).expect("question name parses");
+        let soa_offset = 12 + qname_len + 4;
+
+        let (owner_name, owner_len) = parse_qname_from_dns_packet(&packet, soa_offset).expect("SOA owner parses");
+        assert_eq!(owner_name, ZONE_APEX.trim_end_matches('.'));
+
+        let mut offset = soa_offset + owner_len;
+        assert_eq!(bytes_to_u16_be(&packet[offset..offset + 2]).unwrap(), TYPE_SOA);
+        offset += 8; // TYPE, CLASS, TTL
+        let rdlength = bytes_to_u16_be(&packet[offset..offset + 2]).unwrap() as usize;
+        offset += 2;
+        let rdata_start = offset;
+
+        let (mname, mname_len) = parse_qname_from_dns_packet(&packet, offset).expect("MNAME parses");
+        assert_eq!(mname, SOA_MNAME.trim_end_matches('.'));
+        offset += mname_len;
+        let (rname, rname_len) = parse_qname_from_dns_packet(&packet, offset).expect("RNAME parses");
+        assert_eq!(rname, SOA_RNAME.trim_end_matches('.'));
+        offset += rname_len;
+
+        let serial = u32::from_be_bytes(packet[offset..offset + 4].try_into().unwrap());
+        offset += 4;
+        let refresh = u32::from_be_bytes(packet[offset..offset + 4].try_into().unwrap());
+        offset += 4;
+        let retry = u32::from_be_bytes(packet[offset..offset + 4].try_into().unwrap());
+        offset += 4;
+        let expire = u32::from_be_bytes(packet[offset..offset + 4].try_into().unwrap());
+        offset += 4;
+        let minimum = u32::from_be_bytes(packet[offset..offset + 4].try_into().unwrap());
+        offset += 4;
+        assert_eq!(serial, SOA_SERIAL);
+        assert_eq!(refresh, SOA_REFRESH);
+        assert_eq!(retry, SOA_RETRY);
+        assert_eq!(expire, SOA_EXPIRE);
+        assert_eq!(minimum, SOA_MINIMUM_TTL);
+
+        // RDLENGTH must cover exactly MNAME + RNAME + the five 4-byte fields, nothing more/less.
+        assert_eq!(rdlength, (mname_len + rname_len) + 20);
+        assert_eq!(rdata_start + rdlength, offset);
+        assert_eq!(offset, packet.len());
+    }
+
+    #[test]
+    fn txt_record_rebuilds_a_long_attribute_from_its_255_byte_chunks() {
+        let long_tag = "x".repeat(300);
+        let query = single_question_query("chunked.demo-service.internal", QTYPE_TXT, None);
+        let mut dns_data_map = HashMap::new();
+        dns_data_map.insert(
+            "chunked.demo-service.internal".to_string(),
+            entry_with(vec![], vec![], 1234, vec![long_tag.as_str()]),
+        );
+
+        let packet = build_dns_response_packet_for_transport(&query, &dns_data_map, None);
+
+        let (_qname, qname_len) = parse_qname_from_dns_packet(&packet, 12).expect("question name parses");
+        let answer_offset = 12 + qname_len + 4;
+        let (_owner_name, owner_len) = parse_qname_from_dns_packet(&packet, answer_offset).expect("owner name parses");
+
+        let mut offset = answer_offset + owner_len + 8; // TYPE, CLASS, TTL
+        let rdlength = bytes_to_u16_be(&packet[offset..offset + 2]).unwrap() as usize;
+        offset += 2;
+        let rdata_end = offset + rdlength;
+
+        // Walk every <character-string> in the RDATA and concatenate, since a single logical
+        // attribute over 255 bytes is expected to span more than one of them.
+        let mut reassembled = Vec::new();
+        let mut cursor = offset;
+        while cursor < rdata_end {
+            let len = packet[cursor] as usize;
+            cursor += 1;
+            reassembled.extend_from_slice(&packet[cursor..cursor + len]);
+            cursor += len;
+        }
+        assert_eq!(cursor, rdata_end);
+
+        let reassembled_str = String::from_utf8(reassembled).unwrap();
+        assert_eq!(reassembled_str, format!("port=1234{}", long_tag));
+    }
+
+    #[test]
+    fn truncates_and_sets_tc_when_the_answer_section_exceeds_the_negotiated_udp_size() {
+        let query = single_question_query("many.demo-service.internal", QTYPE_A, None);
+        let mut dns_data_map = HashMap::new();
+        let v4_addresses: Vec<Ipv4Addr> = (0..50).map(|i| Ipv4Addr::new(10, 0, 0, i)).collect();
+        dns_data_map.insert(
+            "many.demo-service.internal".to_string(),
+            entry_with(v4_addresses, vec![], 80, vec!["many.demo-service.internal"]),
+        );
+
+        // 50 A records can't possibly fit in a tiny negotiated payload size.
+        let packet = build_dns_response_packet_for_transport(&query, &dns_data_map, Some(30));
+
+        let (flags, _qdcount, ancount, _nscount, _arcount) = read_header(&packet);
+        assert_ne!(flags & FLAG_TC, 0, "TC bit must be set once the answer section is dropped for size");
+        assert_eq!(ancount, 0, "answers are dropped entirely when truncated, not partially included");
+    }
+
+    #[test]
+    fn does_not_truncate_when_the_answer_section_fits_the_negotiated_size() {
+        let query = single_question_query("one.demo-service.internal", QTYPE_A, None);
+        let mut dns_data_map = HashMap::new();
+        dns_data_map.insert(
+            "one.demo-service.internal".to_string(),
+            entry_with(vec![Ipv4Addr::new(10, 0, 0, 9)], vec![], 80, vec!["one.demo-service.internal"]),
+        );
+
+        let packet = build_dns_response_packet_for_transport(&query, &dns_data_map, Some(DEFAULT_UDP_PAYLOAD_SIZE));
+
+        let (flags, _qdcount, ancount, _nscount, _arcount) = read_header(&packet);
+        assert_eq!(flags & FLAG_TC, 0);
+        assert_eq!(ancount, 1);
+    }
+
+    #[test]
+    fn round_robin_rotation_starts_each_successive_query_at_the_next_address() {
+        let mut dns_data_map = HashMap::new();
+        dns_data_map.insert(
+            "multi.demo-service.internal".to_string(),
+            entry_with(
+                vec![Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2), Ipv4Addr::new(10, 0, 0, 3)],
+                vec![],
+                80,
+                vec!["multi.demo-service.internal"],
+            ),
+        );
+
+        let extract_first_answer_ip = |dns_data_map: &HashMap<String, DnsRecordEntry>| -> Ipv4Addr {
+            let query = single_question_query("multi.demo-service.internal", QTYPE_A, None);
+            let packet = build_dns_response_packet_for_transport(&query, dns_data_map, None);
+            let (_qname, qname_len) = parse_qname_from_dns_packet(&packet, 12).unwrap();
+            let answer_offset = 12 + qname_len + 4;
+            let (_owner_name, owner_len) = parse_qname_from_dns_packet(&packet, answer_offset).unwrap();
+            let rdata_offset = answer_offset + owner_len + 10; // TYPE, CLASS, TTL, RDLENGTH
+            Ipv4Addr::new(packet[rdata_offset], packet[rdata_offset + 1], packet[rdata_offset + 2], packet[rdata_offset + 3])
+        };
+
+        let first = extract_first_answer_ip(&dns_data_map);
+        let second = extract_first_answer_ip(&dns_data_map);
+        let third = extract_first_answer_ip(&dns_data_map);
+        let fourth = extract_first_answer_ip(&dns_data_map);
+
+        assert_eq!(first, Ipv4Addr::new(10, 0, 0, 1));
+        assert_eq!(second, Ipv4Addr::new(10, 0, 0, 2));
+        assert_eq!(third, Ipv4Addr::new(10, 0, 0, 3));
+        assert_eq!(fourth, Ipv4Addr::new(10, 0, 0, 1)); // wraps back around
+    }
+}
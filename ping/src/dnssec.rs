@@ -0,0 +1,161 @@
+// ---------------
+// RFC 9102 DNSSEC Authentication Chain
+// ---------------
+//
+// The `dns` module only ever answers as an authoritative server for our own configured names;
+// it has no notion of DNSSEC. This module adds a DNSSEC-validating *lookup* path instead: given
+// a name, it confirms the answer validates against a recursive resolver, then assembles an RFC
+// 9102 "chain of DNSSEC records" proof (the RRset plus its RRSIG, DNSKEY, and the parent zone's
+// DS) so a downstream verifier can check the signature chain itself, offline.
+
+use std::net::SocketAddr;
+use tokio::task;
+use trust_dns_resolver::config::{NameServerConfig, Protocol, ResolverConfig, ResolverOpts};
+use trust_dns_resolver::proto::rr::{Record, RecordType};
+use trust_dns_resolver::proto::serialize::binary::{BinEncodable, BinEncoder};
+use trust_dns_resolver::Resolver;
+use warp::{Filter, Rejection, Reply};
+
+const UPSTREAM_RESOLVER_ADDRESS: &str = "1.1.1.1:53"; // Cloudflare's public recursive resolver
+
+/// Builds a resolver against our one upstream. `validate` is on for the initial confirmation
+/// that the name's RRset is properly signed (so a bogus or unsigned answer surfaces as an
+/// explicit error rather than being handed back as if trustworthy); it's off for the follow-up
+/// lookups that fetch the raw RRSIG/DNSKEY/DS records the proof needs, since a validating
+/// resolver consumes those internally instead of returning them.
+fn build_resolver(validate: bool) -> Resolver {
+    let socket_addr: SocketAddr = UPSTREAM_RESOLVER_ADDRESS
+        .parse()
+        .expect("UPSTREAM_RESOLVER_ADDRESS is a valid socket address");
+    let name_server = NameServerConfig {
+        socket_addr,
+        protocol: Protocol::Udp,
+        tls_dns_name: None,
+        trust_negative_responses: true,
+    };
+    let mut opts = ResolverOpts::default();
+    opts.validate = validate;
+    Resolver::new(ResolverConfig::from_parts(None, vec![], vec![name_server]), opts)
+        .expect("Failed to create DNS resolver")
+}
+
+/// Finds the zone apex that owns `name`'s records, e.g. "www.example.com" -> "example.com" when
+/// "example.com" is the nearest ancestor (including `name` itself) with an SOA record. DNSKEY
+/// and RRSIG live at the zone apex, not at an arbitrary owner name, so this has to run before
+/// any DNSKEY lookup rather than assuming `name` is already an apex.
+fn find_zone_apex(resolver: &Resolver, name: &str) -> Result<String, String> {
+    let mut candidate = name.trim_end_matches('.').to_string();
+    loop {
+        if let Ok(records) = lookup_records(resolver, &candidate, RecordType::SOA) {
+            if let Some(soa) = records.first() {
+                return Ok(soa.name().to_string().trim_end_matches('.').to_string());
+            }
+        }
+        match candidate.split_once('.') {
+            Some((_, rest)) if !rest.is_empty() => candidate = rest.to_string(),
+            _ => return Err(format!("No SOA record found for '{}' or any of its ancestors", name)),
+        }
+    }
+}
+
+/// Every zone cut from `apex` up to (and including) the root, e.g. "example.com" ->
+/// `["example.com", "com", "."]`. Walking this whole chain (rather than stopping at the first
+/// parent) is what lets the proof cover every delegation hop up to the root trust anchor.
+fn ancestor_zones(apex: &str) -> Vec<String> {
+    let mut zones = Vec::new();
+    let mut current = apex.trim_end_matches('.').to_string();
+    loop {
+        zones.push(current.clone());
+        match current.split_once('.') {
+            Some((_, rest)) => current = rest.to_string(),
+            None => break,
+        }
+    }
+    zones.push(".".to_string());
+    zones
+}
+
+fn lookup_records(resolver: &Resolver, name: &str, record_type: RecordType) -> Result<Vec<Record>, String> {
+    resolver
+        .lookup(name, record_type)
+        .map(|lookup| lookup.record_iter().cloned().collect())
+        .map_err(|e| format!("Lookup of {} {:?} failed: {}", name, record_type, e))
+}
+
+/// Serializes `records` as an RFC 9102 "chain of DNSSEC records": each record in standard DNS
+/// wire format (NAME/TYPE/CLASS/TTL/RDLENGTH/RDATA), prefixed with its own 2-byte big-endian
+/// length so a verifier can split the concatenation back into individual records.
+fn encode_authentication_chain(records: &[Record]) -> Result<Vec<u8>, String> {
+    let mut chain = Vec::new();
+    for record in records {
+        let mut record_bytes = Vec::new();
+        {
+            let mut encoder = BinEncoder::new(&mut record_bytes);
+            record
+                .emit(&mut encoder)
+                .map_err(|e| format!("Failed to encode {:?} record: {}", record.record_type(), e))?;
+        }
+        let length: u16 = record_bytes.len().try_into().map_err(|_| {
+            format!("{:?} record is too large to length-prefix with a u16", record.record_type())
+        })?;
+        chain.extend_from_slice(&length.to_be_bytes());
+        chain.extend_from_slice(&record_bytes);
+    }
+    Ok(chain)
+}
+
+/// Builds the RFC 9102 proof for `name`: its own A RRset plus RRSIG, then DNSKEY+RRSIG+DS for
+/// every zone cut from `name`'s owning apex up to the root, so a verifier can walk the full
+/// delegation chain rather than trusting a single hop.
+fn build_dnssec_proof(name: &str) -> Result<Vec<u8>, String> {
+    let validating_resolver = build_resolver(true);
+    validating_resolver
+        .lookup_ip(name)
+        .map_err(|e| format!("DNSSEC validation failed for '{}': {}", name, e))?;
+
+    let raw_resolver = build_resolver(false);
+    let mut chain_records = Vec::new();
+    chain_records.extend(lookup_records(&raw_resolver, name, RecordType::A)?);
+    chain_records.extend(lookup_records(&raw_resolver, name, RecordType::RRSIG)?);
+
+    let apex = find_zone_apex(&raw_resolver, name)?;
+    let zones = ancestor_zones(&apex);
+    for zone in &zones {
+        chain_records.extend(lookup_records(&raw_resolver, zone, RecordType::DNSKEY)?);
+        chain_records.extend(lookup_records(&raw_resolver, zone, RecordType::RRSIG)?);
+    }
+    // Every zone but the root is delegated from a parent, which attests to it with a DS record
+    // owned at the zone's own apex name; the root has no parent to attest to it (it's the trust
+    // anchor), so it's excluded here.
+    for zone in zones.iter().take(zones.len() - 1) {
+        chain_records.extend(lookup_records(&raw_resolver, zone, RecordType::DS)?);
+    }
+
+    encode_authentication_chain(&chain_records)
+}
+
+/// Handles `GET /dnssec?name=...`: returns the RFC 9102 authentication chain for `name` as raw
+/// bytes, or a plain-text error if validation or any constituent lookup fails.
+async fn dnssec_handler(raw_query: String) -> Result<impl Reply, Rejection> {
+    let name = raw_query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == "name")
+        .map(|(_, value)| value.to_string());
+
+    let body: Vec<u8> = match name {
+        None => b"Missing required 'name' query parameter".to_vec(),
+        Some(name) => match task::spawn_blocking(move || build_dnssec_proof(&name)).await {
+            Ok(Ok(chain)) => chain,
+            Ok(Err(message)) => message.into_bytes(),
+            Err(join_error) => format!("Internal error building DNSSEC proof: {}", join_error).into_bytes(),
+        },
+    };
+
+    Ok(warp::reply::Response::new(body.into()))
+}
+
+/// The `/dnssec` warp route.
+pub fn route() -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::path("dnssec").and(warp::query::raw()).and_then(dnssec_handler)
+}
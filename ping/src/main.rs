@@ -1,4 +1,6 @@
+mod bench;
 mod dns;
+mod dnssec;
 
 use warp::{Filter, Rejection, Reply};
 use reqwest::Client;
@@ -50,12 +52,18 @@ async fn main() {
 
     let check_connections_route = warp::path::end()
         .and_then(check_google_and_amazon_connections);
+    let routes = check_connections_route
+        .or(bench::route())
+        .or(dnssec::route())
+        .boxed();
 
     println!("Warp server started on http://0.0.0.0:3030");
     println!("Accessing the root path (/) will check the connection to google.com and amazon.com.");
+    println!("GET /bench?url=...&n=...&c=... runs a small concurrent load test against url.");
+    println!("GET /dnssec?name=... returns an RFC 9102 DNSSEC authentication chain for name.");
 
-    // Start the warp server.
-    warp::serve(check_connections_route)
-        .run(([0, 0, 0, 0], 3030))
-        .await;
+    // Start the server: TLS and HTTP/1.1+HTTP/2 tuning come from ServerConfig (env-driven), and
+    // shutdown drains in-flight requests on SIGINT/SIGTERM instead of killing them outright.
+    let server_config = server_bootstrap::ServerConfig::from_env(([0, 0, 0, 0], 3030).into());
+    server_bootstrap::serve(server_config, routes).await;
 }
\ No newline at end of file
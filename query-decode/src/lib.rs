@@ -0,0 +1,38 @@
+// ---------------
+// Percent-decoding for query-string values
+// ---------------
+//
+// `ping/src/bench.rs` and `ping-with-google-dns/src/download.rs` each hand-roll their own
+// `key=value&...` query parsing (this project hand-rolls its wire formats elsewhere too, rather
+// than pulling in a query-string derive for a couple of routes), and both needed the same
+// percent-decoding for the values - factored out here so there's one correct implementation
+// instead of two copies drifting apart.
+
+/// Percent-decodes a query-string value: `%XX` escapes and `+` as space. Decodes into raw bytes
+/// first and reassembles them as UTF-8 (invalid sequences are replaced, matching
+/// `String::from_utf8_lossy`), so a multi-byte percent-encoded character like `%C3%A9` comes
+/// back as the intended character instead of one mojibake char per decoded byte.
+pub fn decode_query_value(value: &str) -> String {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '+' => bytes.push(b' '),
+            '%' => {
+                let hex: String = chars.by_ref().take(2).collect();
+                match u8::from_str_radix(&hex, 16) {
+                    Ok(byte) => bytes.push(byte),
+                    Err(_) => {
+                        bytes.push(b'%');
+                        bytes.extend_from_slice(hex.as_bytes());
+                    }
+                }
+            }
+            other => {
+                let mut buf = [0u8; 4];
+                bytes.extend_from_slice(other.encode_utf8(&mut buf).as_bytes());
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
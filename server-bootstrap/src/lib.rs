@@ -0,0 +1,308 @@
+// ---------------
+// Shared server bootstrap: TLS termination, HTTP/1.1 + HTTP/2 tuning, graceful shutdown
+// ---------------
+//
+// Every binary (`ping`, `ping-with-google-dns`, `heavy`) built its own bare
+// `warp::serve(...).run(...)`, with no TLS and no shutdown handling: a SIGTERM during a deploy
+// just killed in-flight requests. This crate factors that bootstrap out once: optional rustls
+// TLS termination (cert/key loaded via rustls-pemfile), tunable HTTP/1.1+HTTP/2 settings, and a
+// SIGINT/SIGTERM-triggered graceful shutdown that stops accepting new connections while letting
+// in-flight requests drain within a deadline.
+
+use hyper::server::conn::AddrIncoming;
+use hyper::service::make_service_fn;
+use hyper::server::accept::Accept;
+use hyper::Server;
+use rustls_pemfile::{certs, pkcs8_private_keys};
+use std::fs::File;
+use std::future::Future;
+use std::io::BufReader;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::{mpsc, watch};
+use tokio_rustls::rustls::{Certificate, PrivateKey, ServerConfig as RustlsServerConfig};
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+use tower::timeout::Timeout;
+use warp::filters::BoxedFilter;
+use warp::{Rejection, Reply};
+
+/// PEM-encoded certificate chain and private key to terminate TLS with, loaded via
+/// rustls-pemfile.
+pub struct TlsSettings {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+/// HTTP/1.1 + HTTP/2 tuning knobs. `request_timeout` bounds how long a single request may take
+/// to complete (applied as a `tower` timeout around the service); `header_read_timeout` bounds
+/// how long hyper waits to finish reading a request's headers.
+pub struct HttpSettings {
+    pub http2_adaptive_window: bool,
+    pub http2_max_concurrent_streams: Option<u32>,
+    pub tcp_nodelay: bool,
+    pub tcp_keepalive: Option<Duration>,
+    pub request_timeout: Duration,
+    pub header_read_timeout: Duration,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        HttpSettings {
+            http2_adaptive_window: true,
+            http2_max_concurrent_streams: Some(250),
+            tcp_nodelay: true,
+            tcp_keepalive: Some(Duration::from_secs(60)),
+            request_timeout: Duration::from_secs(30),
+            header_read_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// How long a graceful shutdown waits for in-flight requests to drain before forcing the
+/// server to stop anyway.
+pub struct ShutdownSettings {
+    pub drain_deadline: Duration,
+}
+
+impl Default for ShutdownSettings {
+    fn default() -> Self {
+        ShutdownSettings { drain_deadline: Duration::from_secs(30) }
+    }
+}
+
+/// Full bootstrap configuration for a warp-based service.
+pub struct ServerConfig {
+    pub bind_addr: SocketAddr,
+    pub tls: Option<TlsSettings>,
+    pub http: HttpSettings,
+    pub shutdown: ShutdownSettings,
+}
+
+impl ServerConfig {
+    /// Builds a config for `bind_addr` with default HTTP and shutdown tuning, and TLS enabled
+    /// only when both `TLS_CERT_PATH` and `TLS_KEY_PATH` are set in the environment.
+    pub fn from_env(bind_addr: SocketAddr) -> Self {
+        let tls = match (std::env::var("TLS_CERT_PATH"), std::env::var("TLS_KEY_PATH")) {
+            (Ok(cert_path), Ok(key_path)) => {
+                Some(TlsSettings { cert_path: PathBuf::from(cert_path), key_path: PathBuf::from(key_path) })
+            }
+            _ => None,
+        };
+
+        ServerConfig {
+            bind_addr,
+            tls,
+            http: HttpSettings::default(),
+            shutdown: ShutdownSettings::default(),
+        }
+    }
+}
+
+/// Resolves once SIGINT or SIGTERM arrives. Unix-only (`tokio::signal::unix`), which is fine for
+/// the containers/VMs these services actually deploy to.
+async fn shutdown_signal() {
+    let mut sigterm = signal(SignalKind::terminate()).expect("failed to install SIGTERM handler");
+    let mut sigint = signal(SignalKind::interrupt()).expect("failed to install SIGINT handler");
+    tokio::select! {
+        _ = sigterm.recv() => {}
+        _ = sigint.recv() => {}
+    }
+    println!("Shutdown signal received; draining in-flight requests...");
+}
+
+/// Drives `server` (a `Server::serve(...).with_graceful_shutdown(...)` future already wired to
+/// `shutdown_rx`) to completion, applying `drain_deadline` only to the time spent *after* the
+/// shutdown signal fires - not to the whole server lifetime. Before the signal fires,
+/// `shutdown_rx.changed()` simply never resolves, so `server` runs unbounded; once it does fire,
+/// `server` is polled again (it's now mid-drain, waiting on in-flight requests) under a timeout
+/// so a stuck connection can't hang the process forever.
+async fn run_until_drained<F>(server: F, mut shutdown_rx: watch::Receiver<bool>, drain_deadline: Duration)
+where
+    F: Future<Output = Result<(), hyper::Error>>,
+{
+    tokio::pin!(server);
+    tokio::select! {
+        biased;
+        _ = shutdown_rx.changed() => {
+            match tokio::time::timeout(drain_deadline, &mut server).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => eprintln!("Server error: {}", e),
+                Err(_) => eprintln!("Graceful shutdown deadline ({:?}) elapsed; forcing exit.", drain_deadline),
+            }
+        }
+        result = &mut server => {
+            match result {
+                Ok(()) => {}
+                Err(e) => eprintln!("Server error: {}", e),
+            }
+        }
+    }
+}
+
+/// Loads a rustls `ServerConfig` (a TLS acceptor config, distinct from the `ServerConfig` this
+/// crate exposes above) from the PEM cert chain and PKCS#8 private key at `tls.cert_path` /
+/// `tls.key_path`.
+fn load_tls_acceptor(tls: &TlsSettings) -> TlsAcceptor {
+    let cert_file = File::open(&tls.cert_path)
+        .unwrap_or_else(|e| panic!("Failed to open TLS cert '{}': {}", tls.cert_path.display(), e));
+    let cert_chain: Vec<Certificate> = certs(&mut BufReader::new(cert_file))
+        .unwrap_or_else(|e| panic!("Failed to parse TLS cert '{}': {}", tls.cert_path.display(), e))
+        .into_iter()
+        .map(Certificate)
+        .collect();
+
+    let key_file = File::open(&tls.key_path)
+        .unwrap_or_else(|e| panic!("Failed to open TLS key '{}': {}", tls.key_path.display(), e));
+    let mut keys: Vec<PrivateKey> = pkcs8_private_keys(&mut BufReader::new(key_file))
+        .unwrap_or_else(|e| panic!("Failed to parse TLS key '{}': {}", tls.key_path.display(), e))
+        .into_iter()
+        .map(PrivateKey)
+        .collect();
+    let private_key = keys.pop().unwrap_or_else(|| panic!("No private key found in '{}'", tls.key_path.display()));
+
+    let rustls_config = RustlsServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(cert_chain, private_key)
+        .expect("Invalid TLS certificate/key pair");
+
+    TlsAcceptor::from(std::sync::Arc::new(rustls_config))
+}
+
+/// A `hyper::server::accept::Accept` over TLS connections. Accepting and handshaking a
+/// connection are both async, but `Accept::poll_accept` is a plain (synchronous) poll, so each
+/// accepted TCP connection's handshake runs on its own spawned task and the finished
+/// `TlsStream` is handed back here over a channel.
+struct TlsIncoming {
+    handshakes: mpsc::Receiver<std::io::Result<TlsStream<tokio::net::TcpStream>>>,
+}
+
+impl TlsIncoming {
+    /// Binds `bind_addr` and accepts TLS connections onto it. Mirrors
+    /// `hyper::server::conn::AddrIncoming`'s error handling: a failed `accept()` (e.g. the
+    /// process is at its file-descriptor limit) or a failed handshake (a port scanner, a health
+    /// check speaking plain HTTP, a client that resets mid-handshake) is logged and retried
+    /// rather than surfaced through `Accept::poll_accept`, where hyper's generic `Server` would
+    /// treat it as fatal and tear down the whole listener.
+    fn bind(bind_addr: SocketAddr, acceptor: TlsAcceptor, tcp_nodelay: bool) -> Self {
+        let (handshake_tx, handshake_rx) = mpsc::channel(16);
+
+        tokio::spawn(async move {
+            let listener = match TcpListener::bind(bind_addr).await {
+                Ok(listener) => listener,
+                Err(e) => {
+                    let _ = handshake_tx.send(Err(e)).await;
+                    return;
+                }
+            };
+
+            loop {
+                let (tcp_stream, _peer_addr) = match listener.accept().await {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        eprintln!("TLS accept() error (retrying): {}", e);
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        continue;
+                    }
+                };
+                if tcp_nodelay {
+                    let _ = tcp_stream.set_nodelay(true);
+                }
+
+                let acceptor = acceptor.clone();
+                let handshake_tx = handshake_tx.clone();
+                tokio::spawn(async move {
+                    match acceptor.accept(tcp_stream).await {
+                        Ok(stream) => {
+                            let _ = handshake_tx.send(Ok(stream)).await;
+                        }
+                        Err(e) => eprintln!("TLS handshake error (dropping connection): {}", e),
+                    }
+                });
+            }
+        });
+
+        TlsIncoming { handshakes: handshake_rx }
+    }
+}
+
+impl Accept for TlsIncoming {
+    type Conn = TlsStream<tokio::net::TcpStream>;
+    type Error = std::io::Error;
+
+    fn poll_accept(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+        self.handshakes.poll_recv(cx)
+    }
+}
+
+/// Binds `config.bind_addr` and serves `routes` until a graceful shutdown completes (SIGINT or
+/// SIGTERM, then up to `config.shutdown.drain_deadline` for in-flight requests), terminating TLS
+/// first if `config.tls` is set.
+pub async fn serve<R>(config: ServerConfig, routes: BoxedFilter<(R,)>)
+where
+    R: Reply + 'static,
+{
+    let service = warp::service(routes);
+    let request_timeout = config.http.request_timeout;
+    let make_svc = make_service_fn(move |_conn| {
+        let service = Timeout::new(service.clone(), request_timeout);
+        async move { Ok::<_, std::convert::Infallible>(service) }
+    });
+
+    let drain_deadline = config.shutdown.drain_deadline;
+
+    // `shutdown_signal()` is consumed by `with_graceful_shutdown` below (triggering the drain),
+    // but we also need to know *when* it fires so `drain_deadline` can be timed from that moment
+    // rather than from process start. A `watch` channel lets both sides observe the same event:
+    // the task below flips it once the signal arrives, and `run_until_drained` races its own
+    // clone of the receiver against the server future to start the deadline at the right time.
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        let _ = shutdown_tx.send(true);
+    });
+
+    match config.tls {
+        None => {
+            let incoming = AddrIncoming::bind(&config.bind_addr)
+                .unwrap_or_else(|e| panic!("Failed to bind {}: {}", config.bind_addr, e));
+            let server = Server::builder(incoming)
+                .http2_adaptive_window(config.http.http2_adaptive_window)
+                .http2_max_concurrent_streams(config.http.http2_max_concurrent_streams)
+                .http1_header_read_timeout(config.http.header_read_timeout)
+                .tcp_nodelay(config.http.tcp_nodelay)
+                .tcp_keepalive(config.http.tcp_keepalive)
+                .serve(make_svc)
+                .with_graceful_shutdown({
+                    let mut shutdown_rx = shutdown_rx.clone();
+                    async move { let _ = shutdown_rx.changed().await; }
+                });
+
+            run_until_drained(server, shutdown_rx, drain_deadline).await;
+        }
+        Some(tls) => {
+            // TLS connections are accepted (and TCP nodelay applied, per `config.http.tcp_nodelay`)
+            // inside `TlsIncoming` itself; a full `tcp_keepalive` here would need the `socket2`
+            // crate to set it on a bare `tokio::net::TcpStream`, which this crate doesn't
+            // otherwise depend on.
+            let incoming = TlsIncoming::bind(config.bind_addr, load_tls_acceptor(&tls), config.http.tcp_nodelay);
+            let server = Server::builder(incoming)
+                .http2_adaptive_window(config.http.http2_adaptive_window)
+                .http2_max_concurrent_streams(config.http.http2_max_concurrent_streams)
+                .http1_header_read_timeout(config.http.header_read_timeout)
+                .serve(make_svc)
+                .with_graceful_shutdown({
+                    let mut shutdown_rx = shutdown_rx.clone();
+                    async move { let _ = shutdown_rx.changed().await; }
+                });
+
+            run_until_drained(server, shutdown_rx, drain_deadline).await;
+        }
+    }
+}